@@ -0,0 +1,372 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Asset pipeline with hash-based cache busting
+//!
+//! With `build.fingerprint_assets` on, every file under the static directory
+//! is written to the output with a short content hash in its name
+//! (`css/style.css` becomes `css/style.3f2a9c1e.css`). References between stylesheets and the assets
+//! they `url(...)` or `@import` are rewritten to the fingerprinted names, and
+//! the resulting mapping is saved as `asset-manifest.json` and exposed to
+//! templates through the `asset(path=...)` function.
+//!
+//! Hosts and crawlers ask for some files by their exact name: `robots.txt`,
+//! `favicon.ico`, `CNAME`, `_headers`, `.nojekyll`, `.well-known/...`. Every
+//! file at the static root or under a dot-name is therefore also written
+//! under its own name next to the fingerprinted copy.
+
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use walkdir::WalkDir;
+
+use crate::cache::{self, AssetRecord, BuildCache};
+
+const MANIFEST_FILE: &str = "asset-manifest.json";
+
+/// Number of hex digits of the SHA-256 kept in fingerprinted names
+const HASH_LEN: usize = 8;
+
+/// Logical asset paths mapped to the paths they were written to
+#[derive(Debug, Default, Clone)]
+pub struct AssetManifest {
+    files: BTreeMap<String, String>,
+}
+
+impl AssetManifest {
+    /// Site-absolute URL of the asset at `path`, relative to the static root
+    pub fn resolve(&self, path: &str) -> Option<String> {
+        self.files
+            .get(path.trim_start_matches('/'))
+            .map(|output| format!("/{}", output))
+    }
+
+    /// Every site path an asset is written to, exact-name copies included
+    pub fn site_paths(&self) -> impl Iterator<Item = String> + '_ {
+        self.files.iter().flat_map(|(key, output)| {
+            let exact = keeps_exact_name(key).then(|| format!("/{}", key));
+            std::iter::once(format!("/{}", output)).chain(exact)
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.files)?)
+    }
+}
+
+/// Tera function `asset(path="css/style.css")`
+pub struct AssetFunction {
+    manifest: Arc<AssetManifest>,
+}
+
+impl AssetFunction {
+    pub fn new(manifest: Arc<AssetManifest>) -> Self {
+        Self { manifest }
+    }
+}
+
+impl tera::Function for AssetFunction {
+    fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+        let path = args
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| tera::Error::msg("asset() requires a string `path` argument"))?;
+
+        self.manifest
+            .resolve(path)
+            .map(tera::Value::String)
+            .ok_or_else(|| tera::Error::msg(format!("asset() could not find '{}'", path)))
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// Fingerprint and write everything under `static_path`, plus `generated`
+/// files produced during the build, into `output_path`.
+///
+/// With `fingerprint` off, files keep their names and only the manifest is
+/// produced, so `asset()` keeps working either way.
+pub fn process(
+    static_path: &Path,
+    generated: Vec<(String, Vec<u8>)>,
+    output_path: &Path,
+    fingerprint: bool,
+    cache: &mut BuildCache,
+) -> Result<AssetManifest> {
+    let mut sources = BTreeMap::new();
+    if static_path.exists() {
+        for entry in WalkDir::new(static_path)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+        {
+            let key = cache::path_key(entry.path().strip_prefix(static_path).unwrap_or(entry.path()));
+            let bytes = fs::read(entry.path())
+                .with_context(|| format!("Failed to read asset: {}", entry.path().display()))?;
+            sources.insert(key, bytes);
+        }
+    }
+    sources.extend(generated);
+
+    let mut pipeline = Pipeline {
+        sources: &sources,
+        fingerprint,
+        outputs: BTreeMap::new(),
+        in_progress: BTreeSet::new(),
+    };
+    for key in sources.keys() {
+        pipeline.resolve(key)?;
+    }
+
+    let mut manifest = AssetManifest::default();
+    for (key, (output, bytes)) in pipeline.outputs {
+        let hash = cache::hash_bytes(&bytes);
+        if !cache.asset_is_fresh(&key, &hash, &output) {
+            let dest = output_path.join(&output);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&dest, &bytes)
+                .with_context(|| format!("Failed to write asset: {}", dest.display()))?;
+        }
+
+        if output != key && keeps_exact_name(&key) {
+            let exact_key = format!("@exact/{}", key);
+            if !cache.asset_is_fresh(&exact_key, &hash, &key) {
+                let dest = output_path.join(&key);
+                fs::write(&dest, &bytes).with_context(|| format!("Failed to write asset: {}", dest.display()))?;
+            }
+            cache.record_asset(
+                &exact_key,
+                AssetRecord {
+                    source: hash.clone(),
+                    output: key.clone(),
+                },
+            );
+        }
+
+        cache.record_asset(
+            &key,
+            AssetRecord {
+                source: hash,
+                output: output.clone(),
+            },
+        );
+        manifest.files.insert(key, output);
+    }
+
+    fs::write(output_path.join(MANIFEST_FILE), manifest.to_json()?)?;
+    Ok(manifest)
+}
+
+struct Pipeline<'a> {
+    sources: &'a BTreeMap<String, Vec<u8>>,
+    fingerprint: bool,
+    /// Output name and final bytes of every asset resolved so far
+    outputs: BTreeMap<String, (String, Vec<u8>)>,
+    /// Stylesheets currently being rewritten, to break `@import` cycles
+    in_progress: BTreeSet<String>,
+}
+
+impl Pipeline<'_> {
+    /// Output name of `key`, processing it (and what it references) first
+    fn resolve(&mut self, key: &str) -> Result<Option<String>> {
+        if let Some((output, _)) = self.outputs.get(key) {
+            return Ok(Some(output.clone()));
+        }
+        let Some(source) = self.sources.get(key) else {
+            return Ok(None);
+        };
+        if !self.fingerprint {
+            self.outputs.insert(key.to_string(), (key.to_string(), source.clone()));
+            return Ok(Some(key.to_string()));
+        }
+        if !self.in_progress.insert(key.to_string()) {
+            return Ok(None);
+        }
+
+        let bytes = if key.ends_with(".css") {
+            let css = String::from_utf8(source.clone())
+                .with_context(|| format!("Stylesheet is not valid UTF-8: {}", key))?;
+            self.rewrite_css(key, &css)?.into_bytes()
+        } else {
+            source.clone()
+        };
+
+        let output = fingerprinted_name(key, &cache::hash_bytes(&bytes)[..HASH_LEN]);
+        self.in_progress.remove(key);
+        self.outputs.insert(key.to_string(), (output.clone(), bytes));
+        Ok(Some(output))
+    }
+
+    /// Point `url(...)` and `@import` references at fingerprinted names
+    fn rewrite_css(&mut self, key: &str, css: &str) -> Result<String> {
+        static REFERENCE: OnceLock<Regex> = OnceLock::new();
+        let re = REFERENCE.get_or_init(|| {
+            Regex::new(r#"url\(\s*(['"]?)([^'")]+)['"]?\s*\)|@import\s+(['"])([^'"]+)['"]"#).unwrap()
+        });
+
+        let base = Path::new(key).parent().unwrap_or(Path::new(""));
+        let mut replacements = HashMap::new();
+
+        for caps in re.captures_iter(css) {
+            let reference = caps.get(2).or_else(|| caps.get(4)).unwrap().as_str().trim();
+            if replacements.contains_key(reference) {
+                continue;
+            }
+            if let Some(target) = local_reference(base, reference) {
+                if let Some(output) = self.resolve(&target)? {
+                    replacements.insert(reference.to_string(), rename_reference(reference, &output));
+                }
+            }
+        }
+
+        let rewritten = re.replace_all(css, |caps: &Captures| {
+            let whole = &caps[0];
+            let reference = caps.get(2).or_else(|| caps.get(4)).unwrap().as_str().trim();
+            match replacements.get(reference) {
+                Some(new) => whole.replacen(reference, new, 1),
+                None => whole.to_string(),
+            }
+        });
+
+        Ok(rewritten.into_owned())
+    }
+}
+
+/// Static-root relative key a stylesheet reference points at, if it's local
+fn local_reference(base: &Path, reference: &str) -> Option<String> {
+    let lower = reference.to_ascii_lowercase();
+    if reference.starts_with('#')
+        || reference.starts_with("//")
+        || lower.starts_with("data:")
+        || lower.contains("://")
+    {
+        return None;
+    }
+
+    let path = reference.split(['?', '#']).next().unwrap_or_default();
+    let joined = match path.strip_prefix('/') {
+        Some(absolute) => PathBuf::from(absolute),
+        None => base.join(path),
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::ParentDir if !normalized.pop() => return None,
+            Component::Normal(part) => normalized.push(part),
+            _ => {}
+        }
+    }
+    Some(cache::path_key(&normalized))
+}
+
+/// Swap the file name of `reference` for the one in `output`, keeping its
+/// directory part and any query or fragment
+fn rename_reference(reference: &str, output: &str) -> String {
+    let end = reference.find(['?', '#']).unwrap_or(reference.len());
+    let (path, suffix) = reference.split_at(end);
+    let dir_end = path.rfind('/').map_or(0, |i| i + 1);
+    let file_name = output.rsplit('/').next().unwrap_or(output);
+    format!("{}{}{}", &path[..dir_end], file_name, suffix)
+}
+
+/// Whether an asset is looked up by name from outside the site: anything at
+/// the static root, and dotfiles or files under dot-directories
+fn keeps_exact_name(key: &str) -> bool {
+    !key.contains('/') || key.split('/').any(|part| part.starts_with('.'))
+}
+
+/// `css/style.css` with hash `3f2a9c1e` becomes `css/style.3f2a9c1e.css`
+fn fingerprinted_name(key: &str, hash: &str) -> String {
+    let (dir, file) = match key.rfind('/') {
+        Some(i) => key.split_at(i + 1),
+        None => ("", key),
+    };
+    match file.rfind('.') {
+        Some(dot) if dot > 0 => format!("{}{}.{}{}", dir, &file[..dot], hash, &file[dot..]),
+        _ => format!("{}{}.{}", dir, file, hash),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprinted_name() {
+        assert_eq!(fingerprinted_name("css/style.css", "3f2a9c1e"), "css/style.3f2a9c1e.css");
+        assert_eq!(fingerprinted_name("js/app.min.js", "abcd1234"), "js/app.min.abcd1234.js");
+        assert_eq!(fingerprinted_name("LICENSE", "abcd1234"), "LICENSE.abcd1234");
+    }
+
+    #[test]
+    fn test_root_and_dotfiles_keep_their_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let mut config = crate::config::Config::default_wokelang();
+        config.build.templates_dir = root.join("templates").to_string_lossy().into_owned();
+        config.build.cache_dir = root.join(".cache").to_string_lossy().into_owned();
+        let static_path = root.join("static");
+        let output = root.join("public");
+        for file in ["robots.txt", ".nojekyll", ".well-known/security.txt", "css/style.css"] {
+            let path = static_path.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+        fs::create_dir_all(&output).unwrap();
+
+        let mut cache = BuildCache::open(&config, &output).unwrap();
+        let manifest = process(&static_path, Vec::new(), &output, true, &mut cache).unwrap();
+        cache.finish().unwrap();
+
+        for file in ["robots.txt", ".nojekyll", ".well-known/security.txt"] {
+            assert_eq!(fs::read_to_string(output.join(file)).unwrap(), file);
+            let fingerprinted = manifest.resolve(file).unwrap();
+            assert_ne!(fingerprinted, format!("/{}", file));
+            assert!(output.join(fingerprinted.trim_start_matches('/')).exists());
+        }
+        assert!(!output.join("css/style.css").exists());
+        assert!(!keeps_exact_name("img/logo.png"));
+    }
+
+    #[test]
+    fn test_css_references_are_rewritten() {
+        let mut sources = BTreeMap::new();
+        sources.insert("img/logo.png".to_string(), b"png".to_vec());
+        sources.insert("css/base.css".to_string(), b"body { margin: 0; }".to_vec());
+        sources.insert(
+            "css/style.css".to_string(),
+            br#"@import "base.css";
+.logo { background: url("../img/logo.png?v=2"); }
+.remote { background: url(https://example.com/x.png); }
+.inline { background: url(data:image/png;base64,AAAA); }
+.missing { background: url(../img/missing.png); }"#
+                .to_vec(),
+        );
+
+        let mut pipeline = Pipeline {
+            sources: &sources,
+            fingerprint: true,
+            outputs: BTreeMap::new(),
+            in_progress: BTreeSet::new(),
+        };
+        let output = pipeline.resolve("css/style.css").unwrap().unwrap();
+        let css = String::from_utf8(pipeline.outputs[&"css/style.css".to_string()].1.clone()).unwrap();
+
+        let logo = pipeline.outputs[&"img/logo.png".to_string()].0.clone();
+        let base = pipeline.outputs[&"css/base.css".to_string()].0.clone();
+        assert!(output.starts_with("css/style.") && output.ends_with(".css"));
+        assert!(css.contains(&format!(r#"@import "{}";"#, base.trim_start_matches("css/"))));
+        assert!(css.contains(&format!(r#"url("../{}?v=2")"#, logo)));
+        assert!(css.contains("url(https://example.com/x.png)"));
+        assert!(css.contains("url(data:image/png;base64,AAAA)"));
+        assert!(css.contains("url(../img/missing.png)"));
+    }
+}
@@ -3,16 +3,34 @@
 
 //! Site builder - orchestrates the build process
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use walkdir::WalkDir;
 
+use crate::assets;
+use crate::bundles;
+use crate::cache::{self, BuildCache, Input, PageRecord};
+use crate::collections;
 use crate::config::Config;
-use crate::content::Page;
-use crate::feeds;
+use crate::content::{Page, Publication};
+use crate::data::{self, LoadData};
+use crate::feeds::{self, FeedChannel};
+use crate::formats;
+use crate::highlight::Highlighter;
+use crate::images::{self, ImageProcessor};
+use crate::links;
+use crate::pagination::{self, Paginator};
+use crate::sections::{self, Section};
+use crate::site::Site;
 use crate::sitemap;
-use crate::templates::Templates;
+use crate::taxonomies::{self, Taxonomy};
+use crate::templates::{PageSummary, RenderContext, Templates};
 
 pub fn init_site(path: &str) -> Result<()> {
     let base = Path::new(path);
@@ -25,6 +43,7 @@ pub fn init_site(path: &str) -> Result<()> {
     fs::create_dir_all(base.join("static"))?;
     fs::create_dir_all(base.join("static/css"))?;
     fs::create_dir_all(base.join("static/js"))?;
+    fs::create_dir_all(base.join("data"))?;
 
     // Create config file
     let config = crate::config::Config::default_wokelang();
@@ -55,11 +74,22 @@ pub fn init_site(path: &str) -> Result<()> {
 }
 ```
 
-Learn more in the [documentation](/docs/getting-started).
+Learn more in the [documentation](@/docs/_index.md).
 "#;
 
     fs::write(base.join("content/index.md"), sample_index)?;
 
+    let sample_docs = r#"---
+title: Documentation
+description: Learn WokeLang step by step
+sort_by: weight
+---
+
+Everything you need to start writing WokeLang.
+"#;
+
+    fs::write(base.join("content/docs/_index.md"), sample_docs)?;
+
     // Create sample template
     let sample_template = r#"<!DOCTYPE html>
 <html lang="{{ site.language }}">
@@ -68,7 +98,7 @@ pub fn init_site(path: &str) -> Result<()> {
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
     <title>{{ page.title }} | {{ site.title }}</title>
     <meta name="description" content="{{ page.description | default(value=site.description) }}">
-    <link rel="stylesheet" href="/css/style.css">
+    <link rel="stylesheet" href="{{ asset(path='css/style.css') }}">
 </head>
 <body>
     <header>
@@ -79,6 +109,7 @@ pub fn init_site(path: &str) -> Result<()> {
             <a href="/playground/">Playground</a>
         </nav>
     </header>
+    {% if page.banner %}<div class="preview-banner">{{ page.banner }}</div>{% endif %}
     <main>
         {{ page.html | safe }}
     </main>
@@ -89,14 +120,89 @@ pub fn init_site(path: &str) -> Result<()> {
 </html>
 "#;
 
+    let sample_style = r#"body {
+    font-family: system-ui, sans-serif;
+    line-height: 1.6;
+    max-width: 48rem;
+    margin: 0 auto;
+    padding: 0 1rem;
+}
+"#;
+
+    fs::write(base.join("static/css/style.css"), sample_style)?;
     fs::write(base.join("templates/index.html"), sample_template)?;
     fs::write(base.join("templates/page.html"), sample_template)?;
 
+    let sample_section = r#"<!DOCTYPE html>
+<html lang="{{ site.language }}">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{{ section.title }} | {{ site.title }}</title>
+    <meta name="description" content="{{ section.description | default(value=site.description) }}">
+    <link rel="stylesheet" href="{{ asset(path='css/style.css') }}">
+</head>
+<body>
+    <header>
+        <h1>{{ site.title }}</h1>
+        <nav>
+            {% for ancestor in section.ancestors %}<a href="{{ ancestor.path }}">{{ ancestor.title }}</a> / {% endfor %}
+            <a href="{{ section.path }}">{{ section.title }}</a>
+        </nav>
+    </header>
+    <main>
+        {{ section.html | safe }}
+        <ul>
+        {% for page in section.pages %}
+            <li><a href="{{ page.path }}">{{ page.title }}</a> {{ page.description }}</li>
+        {% endfor %}
+        {% for subsection in section.subsections %}
+            <li><a href="{{ subsection.path }}">{{ subsection.title }}</a></li>
+        {% endfor %}
+        </ul>
+    </main>
+    <footer>
+        <p>&copy; 2026 {{ site.author }}. Released under PMPL-1.0-or-later.</p>
+    </footer>
+</body>
+</html>
+"#;
+
+    fs::write(base.join("templates/section.html"), sample_section)?;
+
     println!("✓ Site initialized at {}", path);
     Ok(())
 }
 
-pub fn build_site(config: &Config, source: &str, output: &str) -> Result<()> {
+/// Options chosen on the command line rather than in `config.yaml`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuildOptions {
+    /// Include pages marked `draft: true`
+    pub drafts: bool,
+    /// Include pages outside their publishing window: a `date` in the future
+    /// or an `expires` date in the past
+    pub future: bool,
+}
+
+impl BuildOptions {
+    fn includes(&self, publication: Publication) -> bool {
+        match publication {
+            Publication::Published => true,
+            Publication::Draft => self.drafts,
+            Publication::Scheduled(_) | Publication::Expired(_) => self.future,
+        }
+    }
+}
+
+/// How much of a build was rendered rather than reused from the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildStats {
+    pub rendered: usize,
+    /// Pages, sections and listing pagers in the site
+    pub total: usize,
+}
+
+pub fn build_site(config: &Config, source: &str, output: &str, options: &BuildOptions) -> Result<BuildStats> {
     let source_path = Path::new(source);
     let output_path = Path::new(output);
 
@@ -104,66 +210,1031 @@ pub fn build_site(config: &Config, source: &str, output: &str) -> Result<()> {
     fs::create_dir_all(output_path)?;
 
     // Load templates
-    let templates = Templates::load(&config.build.templates_dir)?;
+    let mut templates = Templates::load(&config.build.templates_dir)?;
+    let highlighter = Highlighter::new(&config.highlight)?;
+    let mut cache = BuildCache::open(config, output_path)?;
+    cache.include_in_fingerprint(format!("{:?}", options).as_bytes());
+
+    // Fingerprint static assets first, since pages link to them by hash
+    let assets = assets::process(
+        Path::new(&config.build.static_dir),
+        highlighter.stylesheets()?,
+        output_path,
+        config.build.fingerprint_assets,
+        &mut cache,
+    )?;
+    cache.include_input(Input::Assets, &cache::hash_bytes(assets.to_json()?.as_bytes()));
+    let asset_paths: Vec<String> = assets.site_paths().collect();
+    templates.register_assets(Arc::new(assets));
+
+    let data_dir = Path::new(&config.build.data_dir);
+    let loader = LoadData::new(data_dir, source_path);
+    let data_hash = data::sources_hash(data_dir)?;
+    cache.include_input(Input::Data, &data_hash);
+    // `load_data` reads the data directory too
+    let content_data_hash = data::content_files_hash(source_path, cache.content_data())?;
+    cache.include_input(Input::LoadData, &format!("{}{}", data_hash, content_data_hash));
+    templates.register_data(data::load_dir(data_dir)?, loader.clone());
+
+    let images = Arc::new(ImageProcessor::new(config, source_path, output_path));
+    let image_sources = [Path::new(&config.build.static_dir), source_path];
+    cache.include_input(Input::Images, &images::sources_hash(&image_sources)?);
+    templates.register_images(Arc::clone(&images));
 
-    // Collect all pages
-    let mut pages = Vec::new();
-    for entry in WalkDir::new(source_path)
+    // Collect all content, sorted so output order never depends on the filesystem
+    let (section_paths, page_paths): (Vec<PathBuf>, Vec<PathBuf>) = WalkDir::new(source_path)
+        .sort_by_file_name()
         .into_iter()
         .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().map_or(false, |ext| ext == "md"))
-    {
-        let page = Page::from_file(entry.path())?;
-        pages.push((entry.path().to_path_buf(), page));
-    }
-
-    // Render pages
-    for (path, page) in &pages {
-        let relative = path
-            .strip_prefix(source_path)
-            .unwrap_or(path);
-
-        let output_file = if relative.file_stem().and_then(|s| s.to_str()) == Some("index") {
-            output_path.join(relative.parent().unwrap_or(Path::new(""))).join("index.html")
-        } else {
-            output_path
-                .join(relative.parent().unwrap_or(Path::new("")))
-                .join(format!("{}.html", page.slug))
-        };
+        .filter(|e| e.file_type().is_file() && formats::for_path(e.path()).is_some())
+        .map(|e| e.into_path())
+        .partition(|path| sections::is_section_file(path));
+
+    let mut pages = collect_all(
+        page_paths
+            .par_iter()
+            .map(|path| {
+                Page::from_file(path, &highlighter, &templates, config.build.anchor_links)
+                    .and_then(|mut page| {
+                        let relative = path.strip_prefix(source_path).unwrap_or(path);
+                        assign_urls(&mut page, relative, config)?;
+                        Ok(page)
+                    })
+                    .with_context(|| format!("Failed to parse {}", path.display()))
+            })
+            .collect(),
+    )?;
+
+    let mut sections = collect_all(
+        section_paths
+            .par_iter()
+            .map(|path| {
+                Section::from_file(path, &highlighter, &templates, config.build.anchor_links)
+                    .map(|mut section| {
+                        let relative = path.strip_prefix(source_path).unwrap_or(path);
+                        assign_section_urls(&mut section, relative, config);
+                        section
+                    })
+                    .with_context(|| format!("Failed to parse {}", path.display()))
+            })
+            .collect(),
+    )?;
 
-        fs::create_dir_all(output_file.parent().unwrap())?;
+    // Leave out drafts and pages outside their publishing window unless a
+    // preview asked for them, in which case they carry a banner
+    let now = Utc::now();
+    pages.retain_mut(|page| {
+        let publication = page.publication(now);
+        page.banner = publication.banner();
+        options.includes(publication)
+    });
 
-        let html = templates.render(&page.front_matter.template, page, config)?;
-        fs::write(output_file, html)?;
+    // Only pages that are published get their bundles and images processed
+    collect_all(
+        pages
+            .par_iter_mut()
+            .map(|page| {
+                let path = source_path.join(&page.relative_path);
+                let mut result = bundles::attach(source_path, page);
+                if result.is_ok() && config.images.process_content {
+                    result = images.rewrite_page(page);
+                }
+                result.with_context(|| format!("Failed to parse {}", path.display()))
+            })
+            .collect(),
+    )?;
+
+    collections::validate(config, source_path, &pages)?;
+    sections::link(&mut sections, &mut pages);
+    let mut taxonomies = taxonomies::collect(config, &pages)?;
+    for taxonomy in &mut taxonomies {
+        taxonomy.list_template = templates.pick(format!("{}/list.html", taxonomy.slug), "taxonomy_list.html");
+        taxonomy.single_template =
+            templates.pick(format!("{}/single.html", taxonomy.slug), "taxonomy_single.html");
+    }
+    let published = published_paths(asset_paths, &pages, &sections, &taxonomies);
+    links::resolve(source_path, &mut pages, &mut sections, &published)?;
+    check_unique_outputs(&pages, &sections, &taxonomies)?;
+
+    // Each output is keyed on what its template is shown, so only pages that
+    // list or sit next to a changed page are rendered again. Templates that
+    // look pages up themselves could show anything.
+    cache.include_input(Input::Site, &site_fingerprint(&pages, &sections)?);
+
+    let site = Arc::new(Site::new(pages, sections, taxonomies));
+    templates.register_site(Arc::clone(&site));
+    let (pages, sections, taxonomies) = (&site.pages, &site.sections, &site.taxonomies);
+
+    // Render pages, sections and taxonomies whose inputs changed since the
+    // last build
+    let jobs: Vec<RenderJob> = (0..pages.len())
+        .map(RenderJob::Page)
+        .chain(sections.iter().enumerate().flat_map(|(i, section)| {
+            pagers(section.paginator()).map(move |pager| RenderJob::Section(i, pager))
+        }))
+        .chain(taxonomies.iter().enumerate().flat_map(|(t, taxonomy)| {
+            let terms = taxonomy.terms.iter().enumerate().flat_map(move |(k, term)| {
+                pagers(taxonomy.paginator(term)).map(move |pager| RenderJob::Term(t, k, pager))
+            });
+            std::iter::once(RenderJob::Taxonomy(t)).chain(terms)
+        }))
+        .collect();
+
+    let outcomes = collect_all(
+        jobs.par_iter()
+            .map(|job| {
+                let (target, context) = match *job {
+                    RenderJob::Page(i) => {
+                        let p = &pages[i];
+                        let target = RenderTarget::file(source_path, &p.relative_path, &p.output_path);
+                        (target, templates.page_context(i, &site, config))
+                    }
+                    RenderJob::Section(i, pager) => {
+                        let s = &sections[i];
+                        let target = RenderTarget::file(source_path, &s.relative_path, &s.output_path)
+                            .with_pager(s.paginator(), pager);
+                        (target, templates.section_context(i, pager, &site, config))
+                    }
+                    RenderJob::Taxonomy(t) => {
+                        let taxonomy = &taxonomies[t];
+                        let target = RenderTarget::generated(&taxonomy.path, &taxonomy.output_path);
+                        (target, templates.taxonomy_context(t, &site, config))
+                    }
+                    RenderJob::Term(t, k, pager) => {
+                        let (taxonomy, term) = (&taxonomies[t], &taxonomies[t].terms[k]);
+                        let target = RenderTarget::generated(&term.path, &term.output_path)
+                            .with_pager(taxonomy.paginator(term), pager);
+                        (target, templates.term_context(t, k, pager, &site, config))
+                    }
+                };
+
+                let name = target.name.clone();
+                render_cached(target, context, output_path, &cache, &templates)
+                    .with_context(|| format!("Failed to render {}", name))
+            })
+            .collect(),
+    )?;
+
+    let mut rendered = 0;
+    for (source_key, record) in outcomes {
+        match record {
+            Some(record) => {
+                cache.record_page(&source_key, record);
+                rendered += 1;
+            }
+            None => cache.keep_page(&source_key),
+        }
     }
 
-    // Copy static assets
-    if Path::new(&config.build.static_dir).exists() {
-        copy_dir_all(&config.build.static_dir, output_path)?;
+    bundles::copy_assets(source_path, output_path, pages, &mut cache)?;
+
+    // Keep `page/1/` of paginated listings working as an alias of the first
+    // pager, which lives at the listing's own URL
+    let paginated = sections
+        .iter()
+        .filter_map(|s| Some((s.paginator()?, &s.permalink)))
+        .chain(taxonomies.iter().flat_map(|t| {
+            t.terms.iter().filter_map(|term| Some((t.paginator(term)?, &term.permalink)))
+        }));
+    for (paginator, permalink) in paginated {
+        let alias = paginator.alias_output_path();
+        let alias_file = output_path.join(&alias);
+        fs::create_dir_all(alias_file.parent().unwrap())?;
+        fs::write(&alias_file, pagination::alias_html(permalink))?;
+        cache.record_generated(&cache::path_key(&alias));
     }
 
     // Generate feeds
-    let all_pages: Vec<&Page> = pages.iter().map(|(_, p)| p).collect();
-    feeds::generate_rss(&all_pages, config, output_path)?;
-    feeds::generate_atom(&all_pages, config, output_path)?;
+    let all_pages: Vec<&Page> = pages.iter().collect();
+    let site_channel = FeedChannel::site(config);
+    feeds::generate_rss(&all_pages, &site_channel, config, output_path)?;
+    feeds::generate_atom(&all_pages, &site_channel, config, output_path)?;
+    for taxonomy in taxonomies.iter().filter(|t| t.config.feed) {
+        for term in &taxonomy.terms {
+            let term_pages: Vec<&Page> = term.pages.iter().map(|&i| &pages[i]).collect();
+            let channel = FeedChannel {
+                title: format!("{} - {}", config.site.title, term.name),
+                link: term.permalink.clone(),
+                description: format!("Pages filed under {} in {}", term.name, taxonomy.config.name),
+            };
+            let dir = term.output_path.parent().unwrap_or(Path::new(""));
+            fs::create_dir_all(output_path.join(dir))?;
+            feeds::generate_rss(&term_pages, &channel, config, &output_path.join(dir))?;
+            feeds::generate_atom(&term_pages, &channel, config, &output_path.join(dir))?;
+            for file in ["feed.xml", "atom.xml"] {
+                cache.record_generated(&cache::path_key(&dir.join(file)));
+            }
+        }
+    }
 
     // Generate sitemap
-    sitemap::generate(&all_pages, config, output_path)?;
+    sitemap::generate(&all_pages, sections, taxonomies, output_path)?;
+
+    cache.record_content_data(loader.content_files());
+    cache.finish()?;
 
-    println!("✓ Site built successfully");
+    println!(
+        "✓ Site built successfully ({} of {} pages rendered)",
+        rendered,
+        jobs.len()
+    );
+    Ok(BuildStats {
+        rendered,
+        total: jobs.len(),
+    })
+}
+
+/// One output file to render; paginated listings get a job per pager,
+/// numbered from 1
+enum RenderJob {
+    Page(usize),
+    Section(usize, usize),
+    Taxonomy(usize),
+    Term(usize, usize, usize),
+}
+
+/// Pagers to render for a listing: just the one unless it's paginated
+fn pagers(paginator: Option<Paginator>) -> Range<usize> {
+    paginator.map_or(1..2, |p| p.pagers())
+}
+
+/// Where a rendered output comes from and goes to
+struct RenderTarget {
+    /// Cache key: the content-relative source, or the site path of pages
+    /// generated without one
+    key: String,
+    /// Shown in errors
+    name: String,
+    output: PathBuf,
+}
+
+impl RenderTarget {
+    /// Output rendered from a content file
+    fn file(source_path: &Path, relative: &Path, output: &Path) -> Self {
+        Self {
+            key: cache::path_key(relative),
+            name: source_path.join(relative).display().to_string(),
+            output: output.to_path_buf(),
+        }
+    }
+
+    /// Output with no source file of its own, like a taxonomy listing
+    fn generated(path: &str, output: &Path) -> Self {
+        Self {
+            key: format!("@{}", path),
+            name: path.to_string(),
+            output: output.to_path_buf(),
+        }
+    }
+
+    /// Point the target at pager `pager` of a paginated listing; the first
+    /// pager keeps the listing's own output
+    fn with_pager(mut self, paginator: Option<Paginator>, pager: usize) -> Self {
+        if let Some(paginator) = paginator.filter(|_| pager > 1) {
+            self.key = format!("{}?page={}", self.key, pager);
+            self.name = format!("{} (page {})", self.name, pager);
+            self.output = paginator.output_path(pager);
+        }
+        self
+    }
+}
+
+/// Work out where a page is written and the URL it's published under.
+///
+/// This is the only place page locations are decided; templates, feeds and
+/// the sitemap all read the result from the page. In order of precedence a
+/// page's URL comes from its `path` front matter, its position as a
+/// directory index, a permalink pattern configured for its directory, or its
+/// file name (as `name.html` or, with pretty URLs, `name/`).
+fn assign_urls(page: &mut Page, relative: &Path, config: &Config) -> Result<()> {
+    let dir = relative.parent().unwrap_or(Path::new(""));
+    let section = cache::path_key(dir);
+    let section_url = if section.is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{}/", section)
+    };
+
+    if let Some(slug) = &page.front_matter.slug {
+        page.slug = normalize_slug(slug)?;
+    }
+
+    let is_index = relative.file_stem().and_then(|s| s.to_str()) == Some("index");
+    let path = if let Some(path) = &page.front_matter.path {
+        normalize_url_path(path)?
+    } else if is_index {
+        section_url
+    } else if let Some(pattern) = permalink_pattern(config, &section) {
+        normalize_url_path(&expand_permalink(pattern, page, &section)?)?
+    } else if config.build.pretty_urls {
+        format!("{}{}/", section_url, page.slug)
+    } else {
+        format!("{}{}.html", section_url, page.slug)
+    };
+
+    page.relative_path = relative.to_path_buf();
+    page.output_path = output_file_for(&path);
+    page.permalink = format!("{}{}", config.site.url.trim_end_matches('/'), path);
+    page.path = path;
     Ok(())
 }
 
-fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<()> {
-    fs::create_dir_all(&dst)?;
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let ty = entry.file_type()?;
-        if ty.is_dir() {
-            copy_dir_all(entry.path(), dst.as_ref().join(entry.file_name()))?;
-        } else {
-            fs::copy(entry.path(), dst.as_ref().join(entry.file_name()))?;
+/// Sections always live at their directory's URL
+fn assign_section_urls(section: &mut Section, relative: &Path, config: &Config) {
+    let dir = relative.parent().unwrap_or(Path::new(""));
+    section.dir = cache::path_key(dir);
+    section.path = if section.dir.is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{}/", section.dir)
+    };
+    section.relative_path = relative.to_path_buf();
+    section.output_path = dir.join("index.html");
+    section.permalink = format!("{}{}", config.site.url.trim_end_matches('/'), section.path);
+}
+
+/// Pattern configured for the deepest directory containing `section`
+fn permalink_pattern<'a>(config: &'a Config, section: &str) -> Option<&'a str> {
+    config
+        .permalinks
+        .iter()
+        .filter(|(dir, _)| {
+            let dir = dir.trim_matches('/');
+            dir.is_empty()
+                || section == dir
+                || section.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/'))
+        })
+        .max_by_key(|(dir, _)| dir.trim_matches('/').len())
+        .map(|(_, pattern)| pattern.as_str())
+}
+
+/// Fill in `:year`, `:month`, `:day`, `:section` and `:slug`
+fn expand_permalink(pattern: &str, page: &Page, section: &str) -> Result<String> {
+    let needs_date = [":year", ":month", ":day"].iter().any(|p| pattern.contains(p));
+    let date = page.front_matter.date;
+    if needs_date && date.is_none() {
+        bail!("Permalink pattern '{}' needs a date, but the page has none", pattern);
+    }
+
+    let mut path = pattern.replace(":section", section).replace(":slug", &page.slug);
+    if let Some(date) = date {
+        path = path
+            .replace(":year", &date.format("%Y").to_string())
+            .replace(":month", &date.format("%m").to_string())
+            .replace(":day", &date.format("%d").to_string());
+    }
+    Ok(path)
+}
+
+/// Make `path` site-absolute, treating paths without a file extension as
+/// directories
+fn normalize_url_path(path: &str) -> Result<String> {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if segments.iter().any(|s| *s == "." || *s == ".." || s.contains('\\')) {
+        bail!("Page path '{}' must not contain '.' or '..' segments or backslashes", path);
+    }
+
+    let Some(last) = segments.last() else {
+        return Ok("/".to_string());
+    };
+    let joined = format!("/{}", segments.join("/"));
+    Ok(if last.contains('.') {
+        joined
+    } else {
+        format!("{}/", joined)
+    })
+}
+
+/// A front matter `slug`, which must stay a single segment of the page's
+/// URL so it can't move the page out of its directory
+fn normalize_slug(slug: &str) -> Result<String> {
+    let Ok(normalized) = normalize_url_path(slug) else {
+        bail!("Page slug '{}' must not contain '.' or '..' segments or backslashes", slug);
+    };
+    match normalized.trim_matches('/') {
+        "" => bail!("Page slug '{}' is empty", slug),
+        segment if segment.contains('/') => bail!("Page slug '{}' must not contain '/'", slug),
+        segment => Ok(segment.to_string()),
+    }
+}
+
+/// Output file for a normalized site path: directories get an `index.html`
+fn output_file_for(path: &str) -> PathBuf {
+    let relative = Path::new(path.trim_start_matches('/'));
+    if path.ends_with('/') {
+        relative.join("index.html")
+    } else {
+        relative.to_path_buf()
+    }
+}
+
+/// Render a page, section or listing unless the cache says its output is current.
+///
+/// Returns the source's cache key together with a fresh record, or `None`
+/// when the previous output was kept.
+fn render_cached(
+    target: RenderTarget,
+    context: RenderContext,
+    output_path: &Path,
+    cache: &BuildCache,
+    templates: &Templates,
+) -> Result<(String, Option<PageRecord>)> {
+    let output_key = cache::path_key(&target.output);
+    let context_hash = context.hash();
+    let template = context.template;
+
+    if cache.page_is_fresh(&target.key, &context_hash, template, &output_key) {
+        return Ok((target.key, None));
+    }
+
+    let output_file = output_path.join(&target.output);
+    fs::create_dir_all(output_file.parent().unwrap())?;
+
+    let html = templates.render_context(context)?;
+    fs::write(output_file, &html)?;
+
+    let record = PageRecord {
+        context: context_hash,
+        template: cache.template_hash(template),
+        output: output_key,
+        output_hash: cache::hash_bytes(html.as_bytes()),
+        generated: images::variants_in(&html),
+    };
+    Ok((target.key, Some(record)))
+}
+
+/// Fail when two pages would be written to the same file
+fn check_unique_outputs(pages: &[Page], sections: &[Section], taxonomies: &[Taxonomy]) -> Result<()> {
+    // (what publishes it, output file, site path)
+    let mut outputs: Vec<(String, PathBuf, String)> = Vec::new();
+    let mut add_listing = |source: String, output: &Path, path: &str, paginator: Option<Paginator>| {
+        outputs.push((source.clone(), output.to_path_buf(), path.to_string()));
+        if let Some(paginator) = paginator {
+            outputs.push((source.clone(), paginator.alias_output_path(), paginator.alias_path()));
+            for pager in paginator.pagers().skip(1) {
+                outputs.push((source.clone(), paginator.output_path(pager), paginator.path(pager)));
+            }
+        }
+    };
+
+    for page in pages {
+        add_listing(page.relative_path.display().to_string(), &page.output_path, &page.path, None);
+    }
+    for section in sections {
+        let source = section.relative_path.display().to_string();
+        add_listing(source, &section.output_path, &section.path, section.paginator());
+    }
+    for taxonomy in taxonomies {
+        let source = format!("taxonomy '{}'", taxonomy.config.name);
+        add_listing(source, &taxonomy.output_path, &taxonomy.path, None);
+        for term in &taxonomy.terms {
+            let source = format!("{} term '{}'", taxonomy.config.name, term.name);
+            add_listing(source, &term.output_path, &term.path, taxonomy.paginator(term));
         }
     }
+
+    let mut seen: HashMap<&Path, &str> = HashMap::new();
+    let mut clashes = Vec::new();
+    for (source, output, path) in &outputs {
+        if let Some(other) = seen.insert(output, source) {
+            clashes.push(format!("  - {} and {} both publish to {}", other, source, path));
+        }
+    }
+
+    if !clashes.is_empty() {
+        bail!("Pages with conflicting URLs:\n{}", clashes.join("\n"));
+    }
     Ok(())
 }
+
+/// Site paths of everything a build publishes besides pages and sections
+/// themselves, for checking links against
+fn published_paths(
+    asset_paths: Vec<String>,
+    pages: &[Page],
+    sections: &[Section],
+    taxonomies: &[Taxonomy],
+) -> HashSet<String> {
+    let mut published: HashSet<String> = asset_paths.into_iter().collect();
+    published.extend(["/feed.xml", "/atom.xml", "/sitemap.xml"].map(String::from));
+    for page in pages {
+        published.extend(page.assets.iter().map(|name| bundles::asset_path(page, name)));
+    }
+
+    let mut listings: Vec<(&str, Option<Paginator>)> =
+        sections.iter().map(|s| (s.path.as_str(), s.paginator())).collect();
+    for taxonomy in taxonomies {
+        listings.push((&taxonomy.path, None));
+        for term in &taxonomy.terms {
+            listings.push((&term.path, taxonomy.paginator(term)));
+            if taxonomy.config.feed {
+                published.extend(["feed.xml", "atom.xml"].map(|file| format!("{}{}", term.path, file)));
+            }
+        }
+    }
+    for (path, paginator) in listings {
+        published.insert(path.to_string());
+        if let Some(paginator) = paginator {
+            published.insert(paginator.alias_path());
+            published.extend(paginator.pagers().map(|pager| paginator.path(pager)));
+        }
+    }
+    published
+}
+
+/// Hash of everything `get_page`, `get_section` and `get_taxonomy` can
+/// return: every page's and section's location, front matter, summary and
+/// rendered body
+fn site_fingerprint(pages: &[Page], sections: &[Section]) -> Result<String> {
+    let pages: Vec<_> = pages
+        .iter()
+        .map(|p| (&p.front_matter, PageSummary::new(p), cache::hash_bytes(p.html.as_bytes())))
+        .collect();
+    let sections: Vec<_> = sections
+        .iter()
+        .map(|s| (&s.path, &s.front_matter, cache::hash_bytes(s.html.as_bytes())))
+        .collect();
+    Ok(cache::hash_bytes(&serde_json::to_vec(&(pages, sections))?))
+}
+
+/// Unwrap a batch of results, reporting every failure rather than the first
+fn collect_all<T>(results: Vec<Result<T>>) -> Result<Vec<T>> {
+    let mut values = Vec::with_capacity(results.len());
+    let mut errors = Vec::new();
+
+    for result in results {
+        match result {
+            Ok(value) => values.push(value),
+            Err(e) => errors.push(format!("  - {:#}", e)),
+        }
+    }
+
+    if !errors.is_empty() {
+        bail!("{} page(s) failed to build:\n{}", errors.len(), errors.join("\n"));
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TaxonomyConfig;
+    use crate::testing::TestSite;
+
+    #[test]
+    fn test_incremental_build_matches_clean_build() {
+        let site = TestSite::new();
+
+        site.write(
+            "templates/base.html",
+            r#"<link href="{{ asset(path='css/style.css') }}"><main>{% block body %}{% endblock %}</main>"#,
+        );
+        site.write(
+            "templates/page.html",
+            r#"{% extends "base.html" %}{% block body %}{{ page.html | safe }}{% endblock %}"#,
+        );
+        site.write("static/css/style.css", "body { background: url(../img/bg.png); }");
+        site.write("static/img/bg.png", "png");
+        site.write("templates/shortcodes/note.html", "<aside>{{ body | safe }}</aside>");
+        site.write("content/index.md", "---\ntitle: Home\ntemplate: page.html\n---\n# Home\n{% note() %}Hi{% end %}\n");
+        site.write("content/docs/guide.md", "---\ntitle: Guide\ntemplate: page.html\n---\n# Guide\n");
+
+        assert_eq!(site.build().unwrap(), BuildStats { rendered: 2, total: 2 });
+
+        site.write("content/docs/guide.md", "---\ntitle: Guide\ntemplate: page.html\n---\n# Guide v2\n");
+        assert_eq!(site.build().unwrap().rendered, 1);
+
+        // Shortcode output is part of the bodies of the pages using it
+        site.write("templates/shortcodes/note.html", "<aside class=\"note\">{{ body | safe }}</aside>");
+        assert_eq!(site.build().unwrap().rendered, 1);
+        assert!(site.read("index.html").contains("<aside class=\"note\"><p>Hi</p></aside>"));
+
+        // Touch the shared base template and an asset, delete nothing
+        site.write(
+            "templates/base.html",
+            r#"<link href="{{ asset(path='css/style.css') }}"><body>{% block body %}{% endblock %}</body>"#,
+        );
+        site.write("static/img/bg.png", "png v2");
+        assert_eq!(site.build().unwrap().rendered, 2);
+
+        let clean = site.root.join("clean");
+        fs::remove_dir_all(site.root.join(".cache")).unwrap();
+        site.build_into(&clean, &BuildOptions::default()).unwrap();
+
+        // The Atom feed carries the build time, everything else must match
+        let files = |dir: &Path| -> Vec<(PathBuf, Vec<u8>)> {
+            WalkDir::new(dir)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file() && e.file_name() != "atom.xml")
+                .map(|e| (e.path().strip_prefix(dir).unwrap().to_path_buf(), fs::read(e.path()).unwrap()))
+                .collect()
+        };
+        assert_eq!(files(&site.output), files(&clean));
+    }
+
+    #[test]
+    fn test_page_edits_only_rerender_what_shows_them() {
+        let site = TestSite::new();
+
+        site.write(
+            "templates/page.html",
+            "{{ page.title }}: {{ page.html | safe }} prev={% if page.previous %}{{ page.previous.title }}{% endif %}",
+        );
+        site.write(
+            "templates/section.html",
+            "{{ section.title }}:{% for p in section.pages %} {{ p.title }}{% endfor %}",
+        );
+        site.write("content/_index.md", "---\ntitle: Home\n---\n");
+        site.write("content/about.md", "---\ntitle: About\n---\nAbout us\n");
+        site.write("content/docs/_index.md", "---\ntitle: Docs\nsort_by: weight\n---\n");
+        for (weight, name) in ["one", "two", "three"].iter().enumerate() {
+            site.write(&format!("content/docs/{}.md", name),
+                &format!("---\ntitle: {}\nweight: {}\n---\nThe {} page\n<!-- more -->\nMore\n", name, weight, name),
+            );
+        }
+
+        let stats = site.build().unwrap();
+        assert_eq!(stats, BuildStats { rendered: 6, total: 6 });
+
+        // Only the page itself shows what comes after its summary
+        site.write("content/docs/two.md", "---\ntitle: two\nweight: 1\n---\nThe two page\n<!-- more -->\nLess\n");
+        assert_eq!(site.build().unwrap().rendered, 1);
+
+        // The title shows in the section listing and in both neighbours' links
+        site.write("content/docs/two.md", "---\ntitle: Two\nweight: 1\n---\nThe two page\n<!-- more -->\nLess\n");
+        assert_eq!(site.build().unwrap().rendered, 4);
+        assert!(fs::read_to_string(site.output.join("docs/three.html")).unwrap().ends_with("prev=Two"));
+
+        assert_eq!(site.build().unwrap().rendered, 0);
+    }
+
+    #[test]
+    fn test_inputs_only_rerender_templates_using_them() {
+        let mut site = TestSite::new();
+        site.config.build.fingerprint_assets = true;
+
+        site.write("templates/plain.html", "{{ page.title }}");
+        site.write("templates/data.html", "{{ data.site.name }}");
+        site.write("templates/asset.html", r#"{{ asset(path="style.css") }}"#);
+        site.write("templates/load.html", r#"{{ load_data(path="@/docs/rows.csv") | length }}"#);
+        site.write("data/site.yaml", "name: One\n");
+        site.write("static/style.css", "a {}");
+        site.write("content/docs/rows.csv", "a\n1\n");
+        for name in ["plain", "data", "asset", "load"] {
+            site.write(&format!("content/{}.md", name), &format!("---\ntitle: T\ntemplate: {}.html\n---\n", name));
+        }
+        assert_eq!(site.build().unwrap().rendered, 4);
+
+        site.write("data/site.yaml", "name: Two\n");
+        assert_eq!(site.build().unwrap().rendered, 2);
+        assert_eq!(fs::read_to_string(site.output.join("data.html")).unwrap(), "Two");
+
+        site.write("static/style.css", "b {}");
+        assert_eq!(site.build().unwrap().rendered, 1);
+
+        site.write("content/docs/rows.csv", "a\n1\n2\n");
+        assert_eq!(site.build().unwrap().rendered, 1);
+        assert_eq!(fs::read_to_string(site.output.join("load.html")).unwrap(), "2");
+    }
+
+    #[test]
+    fn test_incremental_build_removes_deleted_pages() {
+        let site = TestSite::new();
+
+        site.write("templates/page.html", "{{ page.html | safe }}");
+        site.write("content/old.md", "---\ntitle: Old\ntemplate: page.html\n---\nOld\n");
+
+        site.build().unwrap();
+        assert!(site.output.join("old.html").exists());
+
+        fs::remove_file(site.content.join("old.md")).unwrap();
+        site.build().unwrap();
+        assert!(!site.output.join("old.html").exists());
+    }
+
+    #[test]
+    fn test_build_reports_every_failing_page() {
+        let site = TestSite::new();
+
+        site.write("templates/page.html", "{{ page.html | safe }}");
+        site.write("content/good.md", "---\ntitle: Good\ntemplate: page.html\n---\nGood\n");
+        site.write("content/bad-one.md", "---\ntitle: [unclosed\n---\nBad\n");
+        site.write("content/bad-two.md", "---\ndescription: no title\n---\nBad\n");
+
+        let err = site.build().unwrap_err().to_string();
+
+        assert!(err.starts_with("2 page(s) failed to build"), "{}", err);
+        assert!(err.contains("bad-one.md"), "{}", err);
+        assert!(err.contains("bad-two.md"), "{}", err);
+    }
+
+    #[test]
+    fn test_unpublished_pages_need_preview_flags() {
+        let site = TestSite::new();
+
+        site.write("templates/page.html", "{% if page.banner %}[{{ page.banner }}]{% endif %}{{ page.html | safe }}");
+        site.write("content/live.md", "---\ntitle: Live\ntemplate: page.html\n---\nLive\n");
+        site.write("content/draft.md", "---\ntitle: Draft\ntemplate: page.html\ndraft: true\n---\nDraft\n");
+        site.write(
+            "content/future.md",
+            "---\ntitle: Future\ntemplate: page.html\ndate: 2999-01-01T00:00:00Z\n---\nFuture\n",
+        );
+        site.write(
+            "content/expired.md",
+            "---\ntitle: Expired\ntemplate: page.html\nexpires: 2000-01-01T00:00:00Z\n---\nExpired\n",
+        );
+
+        site.build().unwrap();
+        assert!(site.output.join("live.html").exists());
+        assert!(!site.output.join("draft.html").exists());
+        assert!(!site.output.join("future.html").exists());
+        assert!(!site.output.join("expired.html").exists());
+        assert!(!fs::read_to_string(site.output.join("sitemap.xml")).unwrap().contains("draft"));
+
+        let preview = BuildOptions {
+            drafts: true,
+            future: true,
+        };
+        site.build_with(&preview).unwrap();
+        assert!(site.read("live.html").starts_with("<p>Live"));
+        assert!(site.read("draft.html").starts_with("[Draft]"));
+        assert!(site.read("future.html").starts_with("[Scheduled for 2999-01-01"));
+        assert!(site.read("expired.html").starts_with("[Expired on 2000-01-01"));
+
+        // Dropping the flags again removes the previews
+        site.build().unwrap();
+        assert!(!site.output.join("draft.html").exists());
+    }
+
+    #[test]
+    fn test_page_urls_follow_directories_and_permalinks() {
+        let site = TestSite::new();
+
+        site.write("templates/page.html", "{{ page.path | safe }} {{ page.permalink | safe }}");
+        site.write("content/index.md", "---\ntitle: Home\ntemplate: page.html\n---\n");
+        site.write("content/docs/index.md", "---\ntitle: Docs\ntemplate: page.html\n---\n");
+        site.write("content/docs/getting-started.md", "---\ntitle: Getting Started\ntemplate: page.html\n---\n");
+
+        site.build().unwrap();
+
+        assert_eq!(site.read("index.html"), "/ https://wokelang.org/");
+        assert_eq!(site.read("docs/index.html"), "/docs/ https://wokelang.org/docs/");
+        assert_eq!(
+            site.read("docs/getting-started.html"),
+            "/docs/getting-started.html https://wokelang.org/docs/getting-started.html"
+        );
+
+        let url = "https://wokelang.org/docs/getting-started.html";
+        assert!(site.read("sitemap.xml").contains(&format!("<loc>{}</loc>", url)));
+        assert!(site.read("feed.xml").contains(&format!("<link>{}</link>", url)));
+        assert!(site.read("atom.xml").contains(&format!("href=\"{}\"", url)));
+
+        let mut site = TestSite::new();
+        site.config.build.pretty_urls = true;
+        site.config
+            .permalinks
+            .insert("blog".to_string(), "/blog/:year/:month/:slug/".to_string());
+
+        site.write("templates/page.html", "{{ page.path | safe }}");
+        site.write("content/docs/foo.md", "---\ntitle: Foo\ntemplate: page.html\n---\n");
+        site.write("content/docs/index.md", "---\ntitle: Docs\ntemplate: page.html\n---\n");
+        site.write(
+            "content/blog/2026-hello.md",
+            "---\ntitle: Hello\ntemplate: page.html\nslug: hello\ndate: 2026-03-07T00:00:00Z\n---\n",
+        );
+        site.write("content/misc/privacy.md", "---\ntitle: Privacy\ntemplate: page.html\npath: /legal/privacy\n---\n");
+
+        site.build().unwrap();
+
+        assert_eq!(site.read("docs/foo/index.html"), "/docs/foo/");
+        assert_eq!(site.read("docs/index.html"), "/docs/");
+        assert_eq!(site.read("blog/2026/03/hello/index.html"), "/blog/2026/03/hello/");
+        assert_eq!(site.read("legal/privacy/index.html"), "/legal/privacy/");
+    }
+
+    #[test]
+    fn test_escaping_slugs_and_conflicting_urls_are_rejected() {
+        let site = TestSite::new();
+
+        site.write("templates/page.html", "{{ page.html | safe }}");
+        site.write("content/docs/a.md", "---\ntitle: A\ntemplate: page.html\nslug: ../../escaped\n---\n");
+        site.write("content/docs/b.md", "---\ntitle: B\ntemplate: page.html\nslug: nested/b\n---\n");
+
+        let err = format!("{:#}", site.build().unwrap_err());
+        assert!(err.contains("Page slug '../../escaped' must not contain '.' or '..' segments"), "{}", err);
+        assert!(err.contains("Page slug 'nested/b' must not contain '/'"), "{}", err);
+        assert!(!site.root.join("escaped.html").exists());
+
+        let site = TestSite::new();
+
+        site.write("templates/page.html", "{{ page.html | safe }}");
+        site.write("content/a.md", "---\ntitle: A\ntemplate: page.html\npath: /same.html\n---\n");
+        site.write("content/b.md", "---\ntitle: B\ntemplate: page.html\nslug: same\n---\n");
+
+        let err = site.build().unwrap_err().to_string();
+        assert!(err.contains("both publish to /same.html"), "{}", err);
+    }
+
+    #[test]
+    fn test_sections_list_pages_and_link_siblings() {
+        let site = TestSite::new();
+
+        site.write(
+            "templates/section.html",
+            "{% for a in section.ancestors %}{{ a.title }}>{% endfor %}{{ section.title }}:\
+             {% for p in section.pages %} {{ p.title }}{% endfor %} |\
+             {% for s in section.subsections %} {{ s.title }}{% endfor %}",
+        );
+        site.write(
+            "templates/page.html",
+            "{% for a in page.ancestors %}{{ a.title }}>{% endfor %}{{ page.title }} \
+             prev={% if page.previous %}{{ page.previous.title }}{% endif %} \
+             next={% if page.next %}{{ page.next.title }}{% endif %}",
+        );
+        site.write("content/_index.md", "---\ntitle: Home\n---\n");
+        site.write("content/docs/_index.md", "---\ntitle: Guide\nsort_by: weight\n---\n");
+        site.write("content/docs/basics.md", "---\ntitle: Basics\nweight: 2\n---\n");
+        site.write("content/docs/intro.md", "---\ntitle: Intro\nweight: 1\n---\n");
+        site.write("content/docs/advanced/_index.md", "---\ntitle: Advanced\n---\n");
+
+        site.build().unwrap();
+
+        assert_eq!(site.read("index.html"), "Home: | Guide");
+        assert_eq!(site.read("docs/index.html"), "Home>Guide: Intro Basics | Advanced");
+        assert_eq!(site.read("docs/advanced/index.html"), "Home>Guide>Advanced: |");
+        assert_eq!(site.read("docs/basics.html"), "Home>Guide>Basics prev=Intro next=");
+        assert!(site.read("sitemap.xml").contains("<loc>https://wokelang.org/docs/advanced/</loc>"));
+    }
+
+    #[test]
+    fn test_taxonomies_list_terms_and_write_feeds() {
+        let mut site = TestSite::new();
+        site.config.taxonomies = vec![TaxonomyConfig { name: "tags".to_string(), feed: true, paginate_by: None }];
+
+        site.write(
+            "templates/page.html",
+            "{% for t in page.taxonomies.tags %}{{ t.name }}={{ t.path | safe }} {% endfor %}",
+        );
+        site.write(
+            "templates/taxonomy_list.html",
+            "{{ taxonomy.name }}:{% for t in taxonomy.terms %} {{ t.name }}({{ t.count }}){% endfor %}",
+        );
+        site.write(
+            "templates/taxonomy_single.html",
+            "{{ taxonomy.name }}/{{ term.name }}:{% for p in term.pages %} {{ p.title }}{% endfor %}",
+        );
+        site.write("templates/tags/list.html", "All tags: {{ taxonomy.terms | length }}");
+        site.write("content/old.md", "---\ntitle: Old\ndate: 2025-01-01T00:00:00Z\ntags: [Consent, Basics]\n---\n");
+        site.write("content/new.md", "---\ntitle: New\ndate: 2026-01-01T00:00:00Z\ntags: [consent]\n---\n");
+
+        site.build().unwrap();
+
+        assert_eq!(site.read("tags/index.html"), "All tags: 2");
+        assert_eq!(site.read("tags/consent/index.html"), "tags/consent: New Old");
+        assert_eq!(site.read("tags/basics/index.html"), "tags/Basics: Old");
+        assert_eq!(site.read("old.html"), "Basics=/tags/basics/ consent=/tags/consent/ ");
+        assert!(site.read("tags/consent/feed.xml").contains("<link>https://wokelang.org/tags/consent/</link>"));
+        assert!(site.read("tags/basics/atom.xml").contains("Old"));
+        assert!(site.read("sitemap.xml").contains("<loc>https://wokelang.org/tags/basics/</loc>"));
+
+        // Dropping the last page with a term removes its listing and feeds
+        site.write("content/old.md", "---\ntitle: Old\ntags: [consent]\n---\n");
+        site.build().unwrap();
+        assert!(!site.output.join("tags/basics/index.html").exists());
+        assert!(!site.output.join("tags/basics/feed.xml").exists());
+    }
+
+    #[test]
+    fn test_paginated_sections_and_terms() {
+        let mut site = TestSite::new();
+        site.config.taxonomies = vec![TaxonomyConfig { name: "tags".to_string(), feed: false, paginate_by: Some(3) }];
+
+        let listing = "{{ paginator.current_index }}/{{ paginator.number_pagers }} of {{ paginator.total_pages }}:\
+                       {% for p in paginator.pages %} {{ p.title }}{% endfor %} \
+                       prev={{ paginator.previous | default(value='') | safe }} \
+                       next={{ paginator.next | default(value='') | safe }}";
+        site.write("templates/page.html", "{{ page.title }}");
+        site.write("templates/section.html", listing);
+        site.write("templates/taxonomy_list.html", "");
+        site.write("templates/taxonomy_single.html", listing);
+        site.write("content/blog/_index.md", "---\ntitle: Blog\nsort_by: title\npaginate_by: 2\n---\n");
+        for name in ["a", "b", "c", "d", "e"] {
+            site.write(&format!("content/blog/{}.md", name),
+                &format!("---\ntitle: {}\ntags: [news]\n---\n", name.to_uppercase()),
+            );
+        }
+
+        site.build().unwrap();
+
+        assert_eq!(site.read("blog/index.html"), "1/3 of 5: A B prev= next=/blog/page/2/");
+        assert_eq!(site.read("blog/page/2/index.html"), "2/3 of 5: C D prev=/blog/ next=/blog/page/3/");
+        assert_eq!(site.read("blog/page/3/index.html"), "3/3 of 5: E prev=/blog/page/2/ next=");
+        let canonical = r#"<link rel="canonical" href="https://wokelang.org/blog/">"#;
+        assert!(site.read("blog/page/1/index.html").contains(canonical));
+        assert_eq!(site.read("tags/news/page/2/index.html"), "2/2 of 5: D E prev=/tags/news/ next=");
+
+        // Fewer pages means fewer pagers, and the surplus ones go away
+        fs::remove_file(site.content.join("blog/e.md")).unwrap();
+        site.build().unwrap();
+        assert_eq!(site.read("blog/page/2/index.html"), "2/2 of 4: C D prev=/blog/ next=");
+        assert!(!site.output.join("blog/page/3/index.html").exists());
+    }
+
+    #[test]
+    fn test_templates_look_up_the_site() {
+        let mut site = TestSite::new();
+        site.config.taxonomies = vec![TaxonomyConfig { name: "tags".to_string(), feed: false, paginate_by: None }];
+
+        site.write(
+            "templates/page.html",
+            r#"{% set intro = get_page(path="docs/intro.md") %}{{ intro.title }}: {{ intro.html | safe }}"#,
+        );
+        site.write(
+            "templates/section.html",
+            concat!(
+                r#"{% set docs = get_section(path="/docs/") %}{% set tags = get_taxonomy(kind="tags") %}"#,
+                "{% for p in docs.pages %}{{ p.title }} {% endfor %}",
+                "{% for t in tags.terms %}{{ t.name }}={{ t.pages | length }}{% endfor %}",
+            ),
+        );
+        site.write("templates/taxonomy_list.html", "");
+        site.write("templates/taxonomy_single.html", "");
+        site.write("content/_index.md", "---\ntitle: Home\n---\n");
+        site.write("content/docs/_index.md", "---\ntitle: Docs\n---\n");
+        site.write("content/docs/intro.md", "---\ntitle: Intro\ntags: [start]\n---\nHello\n");
+        site.write("content/about.md", "---\ntitle: About\n---\n");
+
+        site.build().unwrap();
+        assert_eq!(site.read("about.html"), "Intro: <p>Hello</p>\n");
+        assert_eq!(site.read("index.html"), "Intro start=1");
+
+        // A body-only change elsewhere still reaches pages that look it up
+        site.write("content/docs/intro.md", "---\ntitle: Intro\ntags: [start]\n---\nHello again\n");
+        site.build().unwrap();
+        assert_eq!(site.read("about.html"), "Intro: <p>Hello again</p>\n");
+
+        site.write("templates/page.html", r#"{% set missing = get_page(path="missing.md") %}{{ missing.title }}"#);
+        let err = format!("{:#}", site.build().unwrap_err());
+        assert!(err.contains("get_page() could not find 'missing.md'"), "{}", err);
+    }
+
+    #[test]
+    fn test_responsive_images_in_content_and_templates() {
+        let mut site = TestSite::new();
+        site.config.images.process_content = true;
+        site.config.images.widths = vec![20];
+        site.config.images.formats = vec![];
+
+        let png = |path: &Path, width: u32| {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            image::RgbImage::new(width, 20).save(path).unwrap();
+        };
+        png(&site.content.join("blog/launch/cover.png"), 40);
+        png(&site.root.join("static/shots/editor.png"), 30);
+        site.write(
+            "templates/page.html",
+            "{% set shot = responsive_image(path=\"shots/editor.png\", widths=[10]) %}{{ shot.srcset | safe }}|{{ page.html | safe }}",
+        );
+        site.write(
+            "content/blog/launch/index.md",
+            "---\ntitle: Launch\n---\n![Cover](cover.png)\n",
+        );
+
+        site.build().unwrap();
+        let html = fs::read_to_string(site.output.join("blog/launch/index.html")).unwrap();
+        let (srcset, body) = html.split_once('|').unwrap();
+
+        let variants: Vec<&str> = srcset.split(", ").collect();
+        assert_eq!(variants.len(), 2);
+        assert!(variants[0].starts_with("/processed_images/editor.") && variants[0].ends_with(".10.png 10w"), "{}", srcset);
+        assert!(site.output.join(variants[0].split(' ').next().unwrap().trim_start_matches('/')).exists());
+
+        assert!(body.starts_with("<p><img src=\"/processed_images/cover."), "{}", body);
+
+        // Drafts are left out before their images are processed, so a broken
+        // one can't fail the build
+        site.write("content/blog/wip/broken.png", "not an image");
+        site.write("content/blog/wip/index.md", "---\ntitle: WIP\ndraft: true\n---\n![WIP](broken.png)\n");
+        site.build().unwrap();
+        assert!(!site.output.join("blog/wip/broken.png").exists());
+
+        // Variants of an edited image replace the old ones, and a variant
+        // removed from the output makes its page render again
+        let variant = |html: &str| {
+            let variants = images::variants_in(html);
+            site.output.join(variants.iter().find(|v| v.contains("/editor.")).unwrap())
+        };
+        let old = variant(&html);
+        png(&site.root.join("static/shots/editor.png"), 32);
+        site.build().unwrap();
+        let html = fs::read_to_string(site.output.join("blog/launch/index.html")).unwrap();
+        assert!(!old.exists());
+        assert!(variant(&html).exists());
+
+        fs::remove_file(variant(&html)).unwrap();
+        assert_eq!(site.build().unwrap().rendered, 1);
+        assert!(variant(&html).exists());
+    }
+}
@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Page bundles
+//!
+//! A directory whose page is an `index` file, like `blog/launch/index.md`,
+//! is a bundle: the other files in it that aren't content, such as images
+//! and attachments, belong to that page. They're copied next to the
+//! rendered page and listed as `page.assets`, and relative references to
+//! them in the page body are rewritten to site paths, so they keep working
+//! whatever URL the page ends up at and in listings that show its summary.
+
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use walkdir::WalkDir;
+
+use crate::cache::{self, AssetRecord, BuildCache};
+use crate::content::Page;
+use crate::formats;
+
+/// Find the files bundled with `page`, whose URLs must already be assigned,
+/// and point the page's references to them at their site paths
+pub fn attach(source_path: &Path, page: &mut Page) -> Result<()> {
+    let Some(dir) = bundle_dir(&page.relative_path) else {
+        return Ok(());
+    };
+    page.assets = find_assets(&source_path.join(dir))?;
+    if page.assets.is_empty() {
+        return Ok(());
+    }
+
+    page.html = rewrite_references(&page.html, page);
+    if let Some(summary) = &page.summary {
+        page.summary = Some(rewrite_references(summary, page));
+    }
+    Ok(())
+}
+
+/// Copy every page's bundled files next to its rendered output
+pub fn copy_assets(source_path: &Path, output_path: &Path, pages: &[Page], cache: &mut BuildCache) -> Result<()> {
+    for page in pages.iter().filter(|p| !p.assets.is_empty()) {
+        let source_dir = source_path.join(bundle_dir(&page.relative_path).unwrap_or(Path::new("")));
+        let output_dir = page.output_path.parent().unwrap_or(Path::new(""));
+
+        for name in &page.assets {
+            let source = source_dir.join(name);
+            let bytes = fs::read(&source).with_context(|| format!("Failed to read {}", source.display()))?;
+            let hash = cache::hash_bytes(&bytes);
+            let key = format!("@content/{}", cache::path_key(&page.relative_path.with_file_name(name)));
+            let output = cache::path_key(&output_dir.join(name));
+
+            if !cache.asset_is_fresh(&key, &hash, &output) {
+                let dest = output_path.join(&output);
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&dest, &bytes).with_context(|| format!("Failed to write {}", dest.display()))?;
+            }
+            cache.record_asset(&key, AssetRecord { source: hash, output });
+        }
+    }
+    Ok(())
+}
+
+/// Site path a bundled file is published at: the directory of the page's
+/// output, so `index.md` at `/blog/launch/` puts `cover.png` at
+/// `/blog/launch/cover.png`
+pub fn asset_path(page: &Page, name: &str) -> String {
+    match cache::path_key(page.output_path.parent().unwrap_or(Path::new(""))) {
+        dir if dir.is_empty() => format!("/{}", name),
+        dir => format!("/{}/{}", dir, name),
+    }
+}
+
+/// Source file of the asset bundled with `page` that is published at site
+/// path `path`, if there is one
+pub fn asset_source(source_path: &Path, page: &Page, path: &str) -> Option<PathBuf> {
+    let dir = bundle_dir(&page.relative_path)?;
+    let name = page.assets.iter().find(|name| asset_path(page, name) == path)?;
+    Some(source_path.join(dir).join(name))
+}
+
+/// Directory of a bundle's page: `index.*` anywhere but the content root
+fn bundle_dir(relative: &Path) -> Option<&Path> {
+    let is_index = relative.file_stem().and_then(|s| s.to_str()) == Some("index");
+    relative.parent().filter(|dir| is_index && !dir.as_os_str().is_empty())
+}
+
+/// Files under `dir` that aren't content, as `/`-separated relative paths.
+/// Subdirectories holding a page or section of their own are left to it.
+fn find_assets(dir: &Path) -> Result<Vec<String>> {
+    let mut assets = Vec::new();
+    let entries = WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            let hidden = e.file_name().to_string_lossy().starts_with('.');
+            e.depth() == 0 || !(hidden || e.file_type().is_dir() && has_own_page(e.path()))
+        });
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Failed to read bundle {}", dir.display()))?;
+        if entry.file_type().is_file() && formats::for_path(entry.path()).is_none() {
+            assets.push(cache::path_key(entry.path().strip_prefix(dir).unwrap_or(entry.path())));
+        }
+    }
+    Ok(assets)
+}
+
+fn has_own_page(dir: &Path) -> bool {
+    fs::read_dir(dir).into_iter().flatten().flatten().any(|entry| {
+        let path = entry.path();
+        let stem = path.file_stem().and_then(|s| s.to_str());
+        matches!(stem, Some("index" | "_index")) && formats::for_path(&path).is_some()
+    })
+}
+
+/// Replace `src` and `href` values naming one of the page's assets
+fn rewrite_references(html: &str, page: &Page) -> String {
+    static REFERENCE: OnceLock<Regex> = OnceLock::new();
+    let re = REFERENCE.get_or_init(|| Regex::new(r#"\b(src|href)="([^"]*)""#).unwrap());
+
+    re.replace_all(html, |caps: &Captures| {
+        let (name, rest) = match caps[2].find(['?', '#']) {
+            Some(i) => caps[2].split_at(i),
+            None => (&caps[2], ""),
+        };
+        let name = name.trim_start_matches("./");
+        if page.assets.iter().any(|asset| asset == name) {
+            format!("{}=\"{}{}\"", &caps[1], asset_path(page, name), rest)
+        } else {
+            caps[0].to_string()
+        }
+    })
+    .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{write, TestSite};
+
+    #[test]
+    fn test_find_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("launch");
+        for file in [
+            "index.md",
+            "notes.md",
+            "cover.png",
+            ".DS_Store",
+            "files/spec.pdf",
+            "gallery/_index.md",
+            "gallery/one.jpg",
+        ] {
+            write(&bundle, file, "");
+        }
+
+        assert_eq!(find_assets(&bundle).unwrap(), ["cover.png", "files/spec.pdf"]);
+        assert_eq!(bundle_dir(Path::new("blog/launch/index.md")), Some(Path::new("blog/launch")));
+        assert_eq!(bundle_dir(Path::new("index.md")), None);
+        assert_eq!(bundle_dir(Path::new("blog/launch.md")), None);
+    }
+
+    #[test]
+    fn test_attach_rewrites_references_and_copies_assets() {
+        let site = TestSite::new();
+        site.write("content/blog/launch/index.md", "");
+        site.write("content/blog/launch/cover.png", "png");
+        site.write("content/blog/launch/files/spec.pdf", "pdf");
+
+        let html = "<p><img src=\"cover.png\" alt=\"Cover\" /></p>\n\
+                    <p><a href=\"./files/spec.pdf#page=2\">Spec</a></p>\n";
+        let mut page = Page::new(serde_yaml::from_str("title: Launch").unwrap(), "", html.to_string(), String::new());
+        page.relative_path = "blog/launch/index.md".into();
+        page.output_path = "news/launch/index.html".into();
+        page.summary = Some("<img src=\"cover.png\">".to_string());
+
+        attach(&site.content, &mut page).unwrap();
+        assert_eq!(page.assets, ["cover.png", "files/spec.pdf"]);
+        assert_eq!(
+            page.html,
+            "<p><img src=\"/news/launch/cover.png\" alt=\"Cover\" /></p>\n\
+             <p><a href=\"/news/launch/files/spec.pdf#page=2\">Spec</a></p>\n"
+        );
+        assert_eq!(page.summary.as_deref(), Some("<img src=\"/news/launch/cover.png\">"));
+
+        let copy = |page: &Page| {
+            let mut cache = BuildCache::open(&site.config, &site.output).unwrap();
+            copy_assets(&site.content, &site.output, std::slice::from_ref(page), &mut cache).unwrap();
+            cache.finish().unwrap();
+        };
+        copy(&page);
+        assert_eq!(fs::read_to_string(site.output.join("news/launch/files/spec.pdf")).unwrap(), "pdf");
+
+        // Removing a file from the bundle removes it from the site
+        fs::remove_file(site.content.join("blog/launch/files/spec.pdf")).unwrap();
+        attach(&site.content, &mut page).unwrap();
+        copy(&page);
+        assert_eq!(page.assets, ["cover.png"]);
+        assert!(site.output.join("news/launch/cover.png").exists());
+        assert!(!site.output.join("news/launch/files/spec.pdf").exists());
+    }
+}
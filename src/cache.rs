@@ -0,0 +1,456 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Incremental build cache
+//!
+//! Every build records a manifest of what went into each output: the hash of
+//! the context it was rendered with, the hash of its template chain (the template plus
+//! everything it extends, includes or imports) and a global fingerprint of the
+//! site configuration. On the next build a page is only re-rendered when one of
+//! those inputs changed or its output on disk no longer matches what was
+//! written, so an incremental build produces the same bytes as a clean one.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use walkdir::WalkDir;
+
+use crate::config::Config;
+
+const MANIFEST_FILE: &str = "build-manifest.json";
+
+/// Bumped whenever the manifest layout or rendering semantics change
+const MANIFEST_VERSION: u32 = 4;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct Manifest {
+    version: u32,
+    output_dir: String,
+    global: String,
+    pages: BTreeMap<String, PageRecord>,
+    assets: BTreeMap<String, AssetRecord>,
+    /// Content files read with `load_data`, relative to the content directory
+    content_data: BTreeSet<String>,
+}
+
+impl Manifest {
+    /// Every file the build that wrote the manifest put in the output
+    fn outputs(&self) -> BTreeSet<&str> {
+        self.pages
+            .values()
+            .flat_map(|r| std::iter::once(&r.output).chain(&r.generated))
+            .chain(self.assets.values().map(|r| &r.output))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PageRecord {
+    /// Hash of the page, section or listing and site values shown to its template
+    pub context: String,
+    pub template: String,
+    pub output: String,
+    pub output_hash: String,
+    /// Files the output refers to that only exist for its sake, such as
+    /// processed image variants
+    pub generated: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AssetRecord {
+    pub source: String,
+    pub output: String,
+}
+
+pub struct BuildCache {
+    manifest_path: PathBuf,
+    output_path: PathBuf,
+    previous: Manifest,
+    current: Manifest,
+    global_unchanged: bool,
+    template_hashes: HashMap<String, String>,
+    /// Inputs each template's chain reads beyond its own context
+    template_inputs: HashMap<String, BTreeSet<Input>>,
+}
+
+/// Build inputs that only the templates using them depend on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Input {
+    /// Any page, section or taxonomy, through `get_page`, `get_section` or
+    /// `get_taxonomy`
+    Site,
+    /// The files under the data directory, as `data`
+    Data,
+    /// Files read with `load_data`
+    LoadData,
+    /// The asset manifest behind `asset()`
+    Assets,
+    /// Images `responsive_image()` can process
+    Images,
+}
+
+impl BuildCache {
+    /// Open the cache for a build into `output_path`.
+    ///
+    /// A missing, unreadable or outdated manifest simply yields an empty
+    /// cache, which makes the build behave like a clean one. A changed global
+    /// fingerprint keeps the previous records around only so that stale
+    /// outputs can still be cleaned up.
+    pub fn open(config: &Config, output_path: &Path) -> Result<Self> {
+        let manifest_path = Path::new(&config.build.cache_dir).join(MANIFEST_FILE);
+        let output_dir = output_path.to_string_lossy().into_owned();
+        let global = global_fingerprint(config)?;
+
+        let previous = fs::read_to_string(&manifest_path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<Manifest>(&json).ok())
+            .filter(|m| m.version == MANIFEST_VERSION && m.output_dir == output_dir)
+            .unwrap_or_default();
+        let global_unchanged = previous.global == global;
+        let (template_hashes, template_inputs) = template_hashes(&config.build.templates_dir)?;
+
+        let current = Manifest {
+            version: MANIFEST_VERSION,
+            output_dir,
+            global,
+            ..Default::default()
+        };
+
+        Ok(Self {
+            manifest_path,
+            output_path: output_path.to_path_buf(),
+            previous,
+            current,
+            global_unchanged,
+            template_hashes,
+            template_inputs,
+        })
+    }
+
+    /// Fold build-time data that every page can see, such as the build
+    /// options, into the global fingerprint
+    pub fn include_in_fingerprint(&mut self, data: &[u8]) {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.current.global);
+        hasher.update(data);
+        self.current.global = hex::encode(hasher.finalize());
+        self.global_unchanged = self.previous.global == self.current.global;
+    }
+
+    /// Fold the hash of `input` into the templates that use it, so only
+    /// their pages are re-rendered when it changes
+    pub fn include_input(&mut self, input: Input, input_hash: &str) {
+        for (name, inputs) in &self.template_inputs {
+            if let Some(hash) = self.template_hashes.get_mut(name).filter(|_| inputs.contains(&input)) {
+                *hash = hash_bytes(format!("{}{}", hash, input_hash).as_bytes());
+            }
+        }
+    }
+
+    /// Content files the previous build read with `load_data`. Their hash
+    /// belongs in the `load_data` input, since fresh pages won't read them
+    /// again.
+    pub fn content_data(&self) -> &BTreeSet<String> {
+        &self.previous.content_data
+    }
+
+    /// Remember the content files this build read with `load_data`. Pages
+    /// reused from the previous build didn't run `load_data`, so its files
+    /// are kept too unless everything was re-rendered.
+    pub fn record_content_data(&mut self, mut files: BTreeSet<String>) {
+        if self.global_unchanged {
+            files.extend(self.previous.content_data.iter().cloned());
+        }
+        self.current.content_data = files;
+    }
+
+    /// Hash of a template and everything it pulls in
+    pub fn template_hash(&self, name: &str) -> String {
+        self.template_hashes.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Whether the page at `source` can be reused from the previous build
+    pub fn page_is_fresh(&self, source: &str, context_hash: &str, template: &str, output: &str) -> bool {
+        let Some(record) = self.previous.pages.get(source) else {
+            return false;
+        };
+
+        self.global_unchanged
+            && record.context == context_hash
+            && record.template == self.template_hash(template)
+            && record.output == output
+            && self.output_matches(output, &record.output_hash)
+            && record.generated.iter().all(|file| self.output_path.join(file).is_file())
+    }
+
+    pub fn record_page(&mut self, source: &str, record: PageRecord) {
+        self.current.pages.insert(source.to_string(), record);
+    }
+
+    /// Carry a fresh page's record over from the previous build
+    pub fn keep_page(&mut self, source: &str) {
+        if let Some(record) = self.previous.pages.get(source) {
+            self.current.pages.insert(source.to_string(), record.clone());
+        }
+    }
+
+    /// Whether a static asset with `source_hash` was already copied to `output`
+    pub fn asset_is_fresh(&self, source: &str, source_hash: &str, output: &str) -> bool {
+        let Some(record) = self.previous.assets.get(source) else {
+            return false;
+        };
+
+        record.source == source_hash
+            && record.output == output
+            && self.output_matches(output, source_hash)
+    }
+
+    pub fn record_asset(&mut self, source: &str, record: AssetRecord) {
+        self.current.assets.insert(source.to_string(), record);
+    }
+
+    /// Track a file written outside the page and asset pipelines, such as a
+    /// term's feed, so it's removed once no build produces it any more
+    pub fn record_generated(&mut self, output: &str) {
+        self.current.assets.insert(
+            output.to_string(),
+            AssetRecord {
+                source: String::new(),
+                output: output.to_string(),
+            },
+        );
+    }
+
+    /// Remove outputs of the previous build that no longer have a source and
+    /// persist the manifest for the next build
+    pub fn finish(self) -> Result<()> {
+        let live = self.current.outputs();
+        let stale = self.previous.outputs().into_iter().filter(|output| !live.contains(output));
+
+        for output in stale {
+            let path = self.output_path.join(output);
+            if path.exists() {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove stale output: {}", path.display()))?;
+            }
+        }
+
+        if let Some(parent) = self.manifest_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&self.current)?;
+        fs::write(&self.manifest_path, json).with_context(|| {
+            format!("Failed to write build manifest: {}", self.manifest_path.display())
+        })?;
+
+        Ok(())
+    }
+
+    fn output_matches(&self, output: &str, expected: &str) -> bool {
+        fs::read(self.output_path.join(output)).is_ok_and(|bytes| hash_bytes(&bytes) == expected)
+    }
+}
+
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+pub fn hash_file(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read file: {}", path.display()))?;
+    Ok(hash_bytes(&bytes))
+}
+
+/// Fingerprint of everything that affects every page at once
+fn global_fingerprint(config: &Config) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update(serde_yaml::to_string(config)?);
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Hash every template under `templates_dir` together with its dependencies,
+/// and note which inputs each one reads
+type TemplateHashes = (HashMap<String, String>, HashMap<String, BTreeSet<Input>>);
+
+fn template_hashes(templates_dir: &str) -> Result<TemplateHashes> {
+    let root = Path::new(templates_dir);
+    let mut sources = HashMap::new();
+
+    for entry in WalkDir::new(root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let name = template_name(root, entry.path());
+        let source = fs::read_to_string(entry.path())
+            .with_context(|| format!("Failed to read template: {}", entry.path().display()))?;
+        sources.insert(name, source);
+    }
+
+    let mut hashes = HashMap::new();
+    let mut inputs = HashMap::new();
+    for name in sources.keys() {
+        let mut seen = BTreeSet::new();
+        collect_chain(name, &sources, &mut seen);
+
+        let mut hasher = Sha256::new();
+        for dep in &seen {
+            hasher.update(dep);
+            hasher.update(sources.get(dep).map(String::as_str).unwrap_or_default());
+        }
+        hashes.insert(name.clone(), hex::encode(hasher.finalize()));
+
+        let used = seen.iter().filter_map(|dep| sources.get(dep)).flat_map(|s| inputs_used(s)).collect();
+        inputs.insert(name.clone(), used);
+    }
+
+    Ok((hashes, inputs))
+}
+
+fn collect_chain(name: &str, sources: &HashMap<String, String>, seen: &mut BTreeSet<String>) {
+    if !seen.insert(name.to_string()) {
+        return;
+    }
+    if let Some(source) = sources.get(name) {
+        for dep in template_dependencies(source) {
+            collect_chain(&dep, sources, seen);
+        }
+    }
+}
+
+/// Names referenced through `extends`, `include` and `import` tags
+fn template_dependencies(source: &str) -> Vec<String> {
+    static DEPENDENCY: OnceLock<Regex> = OnceLock::new();
+    let re = DEPENDENCY.get_or_init(|| {
+        Regex::new(r#"\{%-?\s*(?:extends|include|import)\s+["']([^"']+)["']"#).unwrap()
+    });
+
+    re.captures_iter(source).map(|c| c[1].to_string()).collect()
+}
+
+/// Inputs read inside the tags of one template source. Matching names
+/// rather than parsing errs towards re-rendering, e.g. for `"data"` in a
+/// string.
+fn inputs_used(source: &str) -> BTreeSet<Input> {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    static NAME: OnceLock<Regex> = OnceLock::new();
+    let tag = TAG.get_or_init(|| Regex::new(r"(?s)\{\{.*?\}\}|\{%.*?%\}").unwrap());
+    let name = NAME.get_or_init(|| Regex::new(r"\b([A-Za-z_]\w*)(\s*\()?").unwrap());
+
+    let mut used = BTreeSet::new();
+    for tag in tag.find_iter(source).map(|m| m.as_str()) {
+        for caps in name.captures_iter(tag) {
+            // `page.extra.data` is an attribute, not the variable
+            let start = caps.get(0).unwrap().start();
+            if tag[..start].ends_with('.') {
+                continue;
+            }
+            let call = caps.get(2).is_some();
+            used.insert(match &caps[1] {
+                "get_page" | "get_section" | "get_taxonomy" if call => Input::Site,
+                "data" => Input::Data,
+                "load_data" if call => Input::LoadData,
+                "asset" if call => Input::Assets,
+                "responsive_image" if call => Input::Images,
+                _ => continue,
+            });
+        }
+    }
+    used
+}
+
+fn template_name(root: &Path, path: &Path) -> String {
+    path_key(path.strip_prefix(root).unwrap_or(path))
+}
+
+/// Platform independent, `/`-separated form of a relative path
+pub fn path_key(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_dependencies() {
+        let source = r#"{% extends "base.html" %}
+{%- import 'macros.html' as m %}
+{% block body %}{% include "partials/nav.html" %}{% endblock %}"#;
+
+        assert_eq!(
+            template_dependencies(source),
+            vec!["base.html", "macros.html", "partials/nav.html"]
+        );
+    }
+
+    #[test]
+    fn test_template_chain_changes_with_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("base.html"), "<main>{% block body %}{% endblock %}</main>").unwrap();
+        fs::write(dir.path().join("page.html"), r#"{% extends "base.html" %}"#).unwrap();
+        fs::write(dir.path().join("other.html"), "standalone").unwrap();
+        let templates_dir = dir.path().to_str().unwrap();
+
+        let (before, _) = template_hashes(templates_dir).unwrap();
+        fs::write(dir.path().join("base.html"), "<body>{% block body %}{% endblock %}</body>").unwrap();
+        let (after, _) = template_hashes(templates_dir).unwrap();
+
+        assert_ne!(before["page.html"], after["page.html"]);
+        assert_eq!(before["other.html"], after["other.html"]);
+    }
+
+    #[test]
+    fn test_template_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, source: &str| fs::write(dir.path().join(name), source).unwrap();
+        write("nav.html", r#"{% set docs = get_section(path="docs") %}{{ asset(path="nav.css") }}"#);
+        write("page.html", r#"{% include "nav.html" %}{% for l in data.links %}{{ page.extra.data }}{% endfor %}"#);
+        write("gallery.html", r#"{{ responsive_image(path="a.png").src }} {{ load_data(path="x.csv") }}"#);
+        write("other.html", "get_page and data are mentioned, not used {{ page.data }}");
+
+        let (_, inputs) = template_hashes(dir.path().to_str().unwrap()).unwrap();
+        let used = |name: &str| inputs[name].iter().copied().collect::<Vec<_>>();
+        assert_eq!(used("nav.html"), [Input::Site, Input::Assets]);
+        assert_eq!(used("page.html"), [Input::Site, Input::Data, Input::Assets]);
+        assert_eq!(used("gallery.html"), [Input::LoadData, Input::Images]);
+        assert!(used("other.html").is_empty());
+    }
+
+    #[test]
+    fn test_content_data_outlives_reused_builds() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default_wokelang();
+        config.build.cache_dir = dir.path().join("cache").to_string_lossy().into_owned();
+        config.build.templates_dir = dir.path().join("templates").to_string_lossy().into_owned();
+        let output = dir.path().join("public");
+        let files = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<BTreeSet<_>>();
+
+        let mut cache = BuildCache::open(&config, &output).unwrap();
+        cache.record_content_data(files(&["stdlib/modules.csv"]));
+        cache.finish().unwrap();
+
+        // Reused pages don't read their files again, so those stay watched
+        let mut cache = BuildCache::open(&config, &output).unwrap();
+        assert_eq!(*cache.content_data(), files(&["stdlib/modules.csv"]));
+        cache.record_content_data(files(&["extra.json"]));
+        cache.finish().unwrap();
+
+        // Everything re-rendered, so what was read this time is all there is
+        let mut cache = BuildCache::open(&config, &output).unwrap();
+        assert_eq!(*cache.content_data(), files(&["extra.json", "stdlib/modules.csv"]));
+        cache.include_in_fingerprint(b"changed");
+        cache.record_content_data(files(&["extra.json"]));
+        cache.finish().unwrap();
+        assert_eq!(*BuildCache::open(&config, &output).unwrap().content_data(), files(&["extra.json"]));
+    }
+}
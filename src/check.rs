@@ -0,0 +1,349 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Link checking over a built site
+//!
+//! `wokelang-ssg check` reads the output directory rather than the content,
+//! so links written by templates and static files are covered along with
+//! those in pages. Every `href`, `src` and `srcset` URL in every HTML file
+//! must point at a file in the output, and any `#fragment` at an id on that
+//! page. External
+//! links never touch the network: they are only matched against the allow
+//! and deny patterns in the `check` config. Pages nothing links to are
+//! reported as orphans but don't fail the check, since some pages are only
+//! meant to be reached from outside. Pages that only redirect never are.
+
+use anyhow::{Context, Result};
+use glob::Pattern;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use walkdir::WalkDir;
+
+use crate::cache;
+use crate::config::Config;
+use crate::formats;
+use crate::links;
+
+/// Everything found wrong with a built site
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    /// Number of HTML pages scanned
+    pub pages: usize,
+    /// Internal links to missing files or anchors
+    pub broken: Vec<Problem>,
+    /// External links the allow and deny lists refuse
+    pub external: Vec<Problem>,
+    /// Pages no other page links to
+    pub orphans: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Problem {
+    /// Site path of the page the link is on
+    pub page: String,
+    pub link: String,
+    pub reason: String,
+}
+
+/// An HTML file in the output, keyed by its `/`-separated output path
+struct HtmlFile {
+    ids: HashSet<String>,
+    links: Vec<String>,
+    /// Whether the page only redirects elsewhere, like the `page/1/` alias
+    /// of a paginated listing
+    redirect: bool,
+}
+
+impl Report {
+    /// Whether the check should exit with an error
+    pub fn failed(&self) -> bool {
+        !self.broken.is_empty() || !self.external.is_empty()
+    }
+
+    pub fn to_human(&self) -> String {
+        let mut out = format!("Checked {} page(s)\n", self.pages);
+        for (heading, problems) in [("Broken links", &self.broken), ("Refused external links", &self.external)] {
+            if problems.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("\n{} ({}):\n", heading, problems.len()));
+            let mut by_page: BTreeMap<&str, Vec<&Problem>> = BTreeMap::new();
+            for problem in problems {
+                by_page.entry(&problem.page).or_default().push(problem);
+            }
+            for (page, problems) in by_page {
+                out.push_str(&format!("  {}\n", page));
+                for problem in problems {
+                    out.push_str(&format!("    - {}: {}\n", problem.link, problem.reason));
+                }
+            }
+        }
+        if !self.orphans.is_empty() {
+            out.push_str(&format!("\n⚠ Orphan pages, not linked from any other page ({}):\n", self.orphans.len()));
+            for orphan in &self.orphans {
+                out.push_str(&format!("  {}\n", orphan));
+            }
+        }
+        if !self.failed() {
+            out.push_str("\n✓ No broken links\n");
+        }
+        out
+    }
+}
+
+/// Check every link in the site built into `output`
+pub fn check(config: &Config, output: &Path) -> Result<Report> {
+    let compile = |patterns: &[String]| -> Result<Vec<Pattern>> {
+        patterns
+            .iter()
+            .map(|p| Pattern::new(p).with_context(|| format!("Invalid link pattern in check config: {}", p)))
+            .collect()
+    };
+    let allow = compile(&config.check.allow)?;
+    let deny = compile(&config.check.deny)?;
+    let base_url = config.site.url.trim_end_matches('/');
+
+    let mut files = HashSet::new();
+    let mut pages = BTreeMap::new();
+    for entry in WalkDir::new(output).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let key = cache::path_key(entry.path().strip_prefix(output).unwrap_or(entry.path()));
+        if key.ends_with(".html") {
+            let html = fs::read_to_string(entry.path())
+                .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+            pages.insert(key.clone(), HtmlFile::parse(&html));
+        }
+        files.insert(key);
+    }
+
+    let mut report = Report {
+        pages: pages.len(),
+        ..Default::default()
+    };
+    let mut linked = HashSet::new();
+    for (key, page) in &pages {
+        for link in &page.links {
+            let problem = |reason: String| Problem {
+                page: site_path(key),
+                link: link.clone(),
+                reason,
+            };
+
+            let internal = match classify(link, base_url) {
+                Link::External(url) => {
+                    if let Some(reason) = refuse_external(url, &allow, &deny) {
+                        report.external.push(problem(reason.to_string()));
+                    }
+                    continue;
+                }
+                Link::Other => continue,
+                Link::Internal(internal) => internal,
+            };
+
+            match resolve(key, internal, &files, &pages) {
+                Ok(target) => {
+                    if target != *key {
+                        linked.insert(target);
+                    }
+                }
+                Err(reason) => report.broken.push(problem(reason)),
+            }
+        }
+    }
+
+    let exempt: BTreeSet<&str> = config.check.orphans.iter().map(String::as_str).collect();
+    report.orphans = pages
+        .iter()
+        .filter(|(key, page)| {
+            !linked.contains(*key) && *key != "index.html" && !key.ends_with("404.html") && !page.redirect
+        })
+        .map(|(key, _)| site_path(key))
+        .filter(|path| !exempt.contains(path.as_str()))
+        .collect();
+
+    Ok(report)
+}
+
+impl HtmlFile {
+    fn parse(html: &str) -> Self {
+        static LINK: OnceLock<Regex> = OnceLock::new();
+        static REFRESH: OnceLock<Regex> = OnceLock::new();
+        let link = LINK.get_or_init(|| Regex::new(r#"\s(href|src|srcset)="([^"]*)""#).unwrap());
+        let refresh = REFRESH.get_or_init(|| Regex::new(r#"(?i)\shttp-equiv="refresh""#).unwrap());
+
+        let mut links = Vec::new();
+        for caps in link.captures_iter(html) {
+            let value = formats::unescape(&caps[2]);
+            if &caps[1] == "srcset" {
+                // Comma-separated candidates, each a URL and a width or density
+                links.extend(value.split(',').filter_map(|c| c.split_whitespace().next()).map(str::to_string));
+            } else {
+                links.push(value);
+            }
+        }
+
+        Self {
+            ids: links::anchor_ids(html),
+            links,
+            redirect: refresh.is_match(html),
+        }
+    }
+}
+
+enum Link<'a> {
+    /// Site-relative or page-relative, with any base URL stripped
+    Internal(&'a str),
+    External(&'a str),
+    /// `mailto:`, `data:` and the like, which aren't checked
+    Other,
+}
+
+fn classify<'a>(link: &'a str, base_url: &str) -> Link<'a> {
+    if let Some(rest) = link.strip_prefix(base_url).filter(|r| r.is_empty() || r.starts_with(['/', '#', '?'])) {
+        return Link::Internal(if rest.is_empty() { "/" } else { rest });
+    }
+    if link.starts_with("//") || link.starts_with("http://") || link.starts_with("https://") {
+        return Link::External(link);
+    }
+    let has_scheme = link
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.is_empty() && !scheme.contains(['/', '?', '#']));
+    if has_scheme || link.is_empty() {
+        Link::Other
+    } else {
+        Link::Internal(link)
+    }
+}
+
+/// Why an external URL isn't allowed, if it isn't. With no allow list,
+/// anything not denied is fine.
+fn refuse_external(url: &str, allow: &[Pattern], deny: &[Pattern]) -> Option<&'static str> {
+    if deny.iter().any(|p| p.matches(url)) {
+        Some("on the deny list")
+    } else if !allow.is_empty() && !allow.iter().any(|p| p.matches(url)) {
+        Some("not on the allow list")
+    } else {
+        None
+    }
+}
+
+/// The output file an internal link on page `from` points at, checking its
+/// fragment against the target's ids
+fn resolve(
+    from: &str,
+    link: &str,
+    files: &HashSet<String>,
+    pages: &BTreeMap<String, HtmlFile>,
+) -> Result<String, String> {
+    let (path, fragment) = match link.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (link, None),
+    };
+    let path = path.split('?').next().unwrap_or_default();
+
+    let target = if path.is_empty() {
+        from.to_string()
+    } else {
+        let dir = match path.strip_prefix('/') {
+            Some(_) => "",
+            None => from.rsplit_once('/').map_or("", |(dir, _)| dir),
+        };
+        let joined = links::join(dir, path).ok_or("points above the site root")?;
+        let index = match joined.as_str() {
+            "" => "index.html".to_string(),
+            dir => format!("{}/index.html", dir),
+        };
+        // Static hosts serve `/docs/intro` from `docs/intro.html` as well
+        let candidates = if path.ends_with('/') || joined.is_empty() {
+            vec![index]
+        } else {
+            vec![format!("{}.html", joined), joined, index]
+        };
+        candidates
+            .into_iter()
+            .find(|candidate| files.contains(candidate))
+            .ok_or("no such file in the output")?
+    };
+
+    match (fragment, pages.get(&target)) {
+        (Some(fragment), Some(page)) if !fragment.is_empty() && fragment != "top" && !page.ids.contains(fragment) => {
+            Err(format!("no anchor #{} on {}", fragment, site_path(&target)))
+        }
+        _ => Ok(target),
+    }
+}
+
+/// How a page's output path appears in links: `docs/index.html` is `/docs/`
+fn site_path(key: &str) -> String {
+    format!("/{}", key.strip_suffix("index.html").unwrap_or(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::write;
+
+    #[test]
+    fn test_check_links_anchors_and_orphans() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "index.html",
+            r##"<link href="&#x2F;style.css"><a href="/docs/">Docs</a> <a href="docs/intro.html#setup">Setup</a>
+               <a href="https://wokelang.org/docs/intro.html">Abs</a> <a href="#top">Top</a> <a href="mailto:a@b.c">Mail</a>
+               <a href="/missing.html">Missing</a> <a href="/docs/intro.html#nope">Nope</a> <img src="../x.png">
+               <a href="https://github.com/hyperpolymath/wokelang">Repo</a> <a href="https://evil.example/">Bad</a>
+               <a href="https://example.org/">Elsewhere</a> <img data-src="/lazy.png" data-href="/x"
+               srcset="/processed_images/a.480.webp 480w, /processed_images/gone.960.webp 960w">"##,
+        );
+        write(root, "style.css", "");
+        write(root, "processed_images/a.480.webp", "");
+        write(root, "blog/index.html", "");
+        write(root, "blog/page/1/index.html", &crate::pagination::alias_html("https://wokelang.org/blog/"));
+        write(root, "docs/index.html", r##"<a href="intro.html">Intro</a> <a href="/docs/intro#setup">Setup</a> <a href="?page=2#list">List</a> <ul id="list">"##);
+        write(root, "docs/intro.html", r#"<h2 id="setup">Setup</h2> <a href="../">Home</a>"#);
+        write(root, "stray.html", "");
+        write(root, "hidden/index.html", "");
+        write(root, "404.html", "");
+
+        let mut config = Config::default_wokelang();
+        config.check.allow = vec!["https://github.com/hyperpolymath/*".to_string(), "https://evil.example/*".to_string()];
+        config.check.deny = vec!["https://evil.example/*".to_string()];
+        config.check.orphans = vec!["/hidden/".to_string()];
+        let report = check(&config, root).unwrap();
+
+        assert_eq!(report.pages, 8);
+        let broken: Vec<(&str, &str)> = report.broken.iter().map(|p| (p.link.as_str(), p.reason.as_str())).collect();
+        assert_eq!(
+            broken,
+            [
+                ("/missing.html", "no such file in the output"),
+                ("/docs/intro.html#nope", "no anchor #nope on /docs/intro.html"),
+                ("../x.png", "points above the site root"),
+                ("/processed_images/gone.960.webp", "no such file in the output"),
+            ]
+        );
+        let external: Vec<(&str, &str)> = report.external.iter().map(|p| (p.link.as_str(), p.reason.as_str())).collect();
+        assert_eq!(
+            external,
+            [("https://evil.example/", "on the deny list"), ("https://example.org/", "not on the allow list")]
+        );
+        assert_eq!(report.orphans, ["/stray.html"]);
+        assert!(report.failed());
+
+        let human = report.to_human();
+        assert!(human.contains("Broken links (4):\n  /\n    - /missing.html: no such file in the output\n"), "{}", human);
+        assert!(human.contains("Orphan pages, not linked from any other page (1):\n  /stray.html\n"), "{}", human);
+
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["external"][0]["page"], "/");
+        assert_eq!(json["orphans"], serde_json::json!(["/stray.html"]));
+    }
+}
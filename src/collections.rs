@@ -0,0 +1,297 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Content collections
+//!
+//! A collection ties a content directory to a schema of front matter fields,
+//! so that, say, every release note has to carry a version and a date. Fields
+//! are looked up among the regular front matter keys first and in `extra`
+//! second. Every page is checked before anything is rendered, and all
+//! problems are reported together, grouped by file.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, NaiveDate};
+use serde_yaml::Value;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::cache;
+use crate::config::{CollectionConfig, Config, FieldSchema, FieldType};
+use crate::content::Page;
+
+/// Fail with a per-file, per-field report unless every page conforms to the
+/// schema of the collection it belongs to
+pub fn validate(config: &Config, source_path: &Path, pages: &[Page]) -> Result<()> {
+    if config.collections.is_empty() {
+        return Ok(());
+    }
+
+    let mut known_pages = HashSet::new();
+    for page in pages {
+        known_pages.insert(cache::path_key(&page.relative_path));
+        known_pages.insert(page.path.clone());
+    }
+
+    let mut report = Vec::new();
+    let mut failing = 0;
+    for page in pages {
+        let Some((name, collection)) = collection_for(config, page) else {
+            continue;
+        };
+
+        let problems = check_page(page, collection, &known_pages)?;
+        if !problems.is_empty() {
+            failing += 1;
+            report.push(format!("  {} ({})", source_path.join(&page.relative_path).display(), name));
+            report.extend(problems.iter().map(|p| format!("    - {}", p)));
+        }
+    }
+
+    if failing > 0 {
+        bail!(
+            "{} page(s) don't match their collection schema:\n{}",
+            failing,
+            report.join("\n")
+        );
+    }
+    Ok(())
+}
+
+/// Collection with the deepest directory containing the page
+fn collection_for<'a>(config: &'a Config, page: &Page) -> Option<(&'a str, &'a CollectionConfig)> {
+    let dir = cache::path_key(page.relative_path.parent().unwrap_or(Path::new("")));
+    config
+        .collections
+        .iter()
+        .filter(|(_, collection)| {
+            let root = collection.dir.trim_matches('/');
+            root.is_empty() || dir == root || dir.strip_prefix(root).is_some_and(|rest| rest.starts_with('/'))
+        })
+        .max_by_key(|(_, collection)| collection.dir.trim_matches('/').len())
+        .map(|(name, collection)| (name.as_str(), collection))
+}
+
+fn check_page(page: &Page, collection: &CollectionConfig, known_pages: &HashSet<String>) -> Result<Vec<String>> {
+    let front_matter = serde_yaml::to_value(&page.front_matter)?;
+    let mut problems = Vec::new();
+
+    for (field, schema) in &collection.fields {
+        let value = front_matter
+            .get(field.as_str())
+            .filter(|v| !is_blank(v))
+            .or_else(|| page.front_matter.extra.get(field).filter(|v| !is_blank(v)));
+
+        match value {
+            None if schema.required => problems.push(format!("{}: required field is missing", field)),
+            None => {}
+            Some(value) => {
+                if let Some(problem) = check_value(value, schema, known_pages) {
+                    problems.push(format!("{}: {}", field, problem));
+                }
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+/// What's wrong with `value`, if anything
+fn check_value(value: &Value, schema: &FieldSchema, known_pages: &HashSet<String>) -> Option<String> {
+    match schema.kind {
+        FieldType::Any => None,
+        FieldType::String => (!value.is_string()).then(|| format!("expected a string, got {}", describe(value))),
+        FieldType::Number => (!value.is_number()).then(|| format!("expected a number, got {}", describe(value))),
+        FieldType::Boolean => (!value.is_bool()).then(|| format!("expected true or false, got {}", describe(value))),
+        FieldType::Date => match value.as_str() {
+            Some(text) if is_date(text) => None,
+            _ => Some(format!("expected a date like 2026-01-31, got {}", describe(value))),
+        },
+        FieldType::List => (!value.is_sequence()).then(|| format!("expected a list, got {}", describe(value))),
+        FieldType::Enum => {
+            if schema.values.is_empty() {
+                return Some("enum fields need a list of allowed `values` in the schema".to_string());
+            }
+            match value.as_str() {
+                Some(text) if schema.values.iter().any(|v| v == text) => None,
+                _ => Some(format!(
+                    "expected one of {}, got {}",
+                    schema.values.join(", "),
+                    describe(value)
+                )),
+            }
+        }
+        FieldType::Reference => {
+            let targets: Vec<&Value> = match value {
+                Value::Sequence(items) => items.iter().collect(),
+                other => vec![other],
+            };
+            let missing: Vec<String> = targets
+                .into_iter()
+                .filter(|target| {
+                    target
+                        .as_str()
+                        .map(|path| path.strip_prefix("@/").unwrap_or(path))
+                        .is_none_or(|path| !known_pages.contains(path))
+                })
+                .map(describe)
+                .collect();
+            (!missing.is_empty()).then(|| format!("no page found for {}", missing.join(", ")))
+        }
+    }
+}
+
+/// Null, empty strings and empty lists count as not given
+fn is_blank(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.is_empty(),
+        Value::Sequence(items) => items.is_empty(),
+        _ => false,
+    }
+}
+
+fn is_date(text: &str) -> bool {
+    DateTime::parse_from_rfc3339(text).is_ok() || NaiveDate::parse_from_str(text, "%Y-%m-%d").is_ok()
+}
+
+/// Short rendering of a value for diagnostics
+fn describe(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("\"{}\"", s),
+        Value::Sequence(_) => "a list".to_string(),
+        Value::Mapping(_) => "a mapping".to_string(),
+        other => serde_yaml::to_string(other).unwrap_or_default().trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn page(relative: &str, front_matter: &str) -> Page {
+        let mut page = Page::new(serde_yaml::from_str(front_matter).unwrap(), "", String::new(), String::new());
+        page.relative_path = PathBuf::from(relative);
+        page
+    }
+
+    /// What `check_value` says about YAML `value` under schema `schema`
+    fn check(value: &str, schema: &str) -> Option<String> {
+        let known_pages = HashSet::from(["releases/0.2.md".to_string(), "/releases/0.2/".to_string()]);
+        let schema: FieldSchema = serde_yaml::from_str(schema).unwrap();
+        check_value(&serde_yaml::from_str(value).unwrap(), &schema, &known_pages)
+    }
+
+    #[test]
+    fn test_field_types() {
+        assert_eq!(check("anything: [1]", "type: any"), None);
+        assert_eq!(check("'0.2'", "type: string"), None);
+        assert_eq!(check("0.2", "type: string"), Some("expected a string, got 0.2".into()));
+        assert_eq!(check("3", "type: number"), None);
+        assert_eq!(check("three", "type: number"), Some("expected a number, got \"three\"".into()));
+        assert_eq!(check("true", "type: boolean"), None);
+        assert_eq!(check("yes please", "type: boolean"), Some("expected true or false, got \"yes please\"".into()));
+        assert_eq!(check("2026-01-31", "type: date"), None);
+        assert_eq!(check("2026-01-31T10:00:00Z", "type: date"), None);
+        assert_eq!(
+            check("31/01/2026", "type: date"),
+            Some("expected a date like 2026-01-31, got \"31/01/2026\"".into())
+        );
+        assert_eq!(check("[a, b]", "type: list"), None);
+        assert_eq!(check("{a: b}", "type: list"), Some("expected a list, got a mapping".into()));
+    }
+
+    #[test]
+    fn test_enum_values() {
+        let schema = "{ type: enum, values: [beta, stable] }";
+        assert_eq!(check("stable", schema), None);
+        assert_eq!(check("wip", schema), Some("expected one of beta, stable, got \"wip\"".into()));
+        assert_eq!(
+            check("stable", "type: enum"),
+            Some("enum fields need a list of allowed `values` in the schema".into())
+        );
+    }
+
+    #[test]
+    fn test_references() {
+        assert_eq!(check("releases/0.2.md", "type: reference"), None);
+        assert_eq!(check("'@/releases/0.2.md'", "type: reference"), None);
+        assert_eq!(check("/releases/0.2/", "type: reference"), None);
+        assert_eq!(
+            check("[releases/0.2.md, releases/0.1.md, 3]", "type: reference"),
+            Some("no page found for \"releases/0.1.md\", 3".into())
+        );
+    }
+
+    #[test]
+    fn test_required_fields_and_extra_lookup() {
+        let collection: CollectionConfig = serde_yaml::from_str(
+            "dir: releases\nfields:\n  \
+               title: { type: string, required: true }\n  \
+               version: { type: string, required: true }\n  \
+               codename: { type: string, required: true }\n  \
+               notes: { type: list }\n",
+        )
+        .unwrap();
+        let page = page(
+            "releases/0.3.md",
+            "title: '0.3'\nextra:\n  version: '0.3'\n  codename: ''\n  notes: []\n",
+        );
+
+        assert_eq!(
+            check_page(&page, &collection, &HashSet::new()).unwrap(),
+            ["codename: required field is missing"]
+        );
+    }
+
+    #[test]
+    fn test_deepest_collection_wins() {
+        let mut config = Config::default_wokelang();
+        config.collections = serde_yaml::from_str("all: { dir: '' }\nreleases: { dir: /releases/ }\n").unwrap();
+
+        let name = |relative: &str| collection_for(&config, &page(relative, "title: x")).map(|(name, _)| name);
+        assert_eq!(name("releases/0.3.md"), Some("releases"));
+        assert_eq!(name("releases/old/0.1.md"), Some("releases"));
+        assert_eq!(name("releases-archive/0.1.md"), Some("all"));
+        assert_eq!(name("about.md"), Some("all"));
+    }
+
+    #[test]
+    fn test_validate_reports_every_nonconforming_page() {
+        let mut config = Config::default_wokelang();
+        config.collections = serde_yaml::from_str(
+            "releases:\n  dir: releases\n  fields:\n    \
+               version: { type: string, required: true }\n    \
+               date: { type: date, required: true }\n    \
+               status: { type: enum, values: [beta, stable] }\n    \
+               supersedes: { type: reference }\n",
+        )
+        .unwrap();
+        let pages = [
+            page("about.md", "title: About"),
+            page(
+                "releases/0.2.md",
+                "title: '0.2'\ndate: 2026-01-01T00:00:00Z\nextra:\n  version: '0.2'\n  status: stable\n",
+            ),
+            page(
+                "releases/0.3.md",
+                "title: '0.3'\ndate: 2026-02-01T00:00:00Z\nextra:\n  version: '0.3'\n  \
+                 supersedes: '@/releases/0.2.md'\n",
+            ),
+            page(
+                "releases/0.4.md",
+                "title: '0.4'\nextra:\n  status: wip\n  supersedes: [releases/0.3.md, releases/0.1.md]\n",
+            ),
+        ];
+
+        let err = validate(&config, Path::new("content"), &pages).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "1 page(s) don't match their collection schema:\n  content/releases/0.4.md (releases)\n    \
+             - date: required field is missing\n    \
+             - status: expected one of beta, stable, got \"wip\"\n    \
+             - supersedes: no page found for \"releases/0.1.md\"\n    \
+             - version: required field is missing"
+        );
+    }
+}
@@ -5,6 +5,7 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -12,6 +13,23 @@ pub struct Config {
     pub site: SiteConfig,
     #[serde(default)]
     pub build: BuildConfig,
+    #[serde(default)]
+    pub highlight: HighlightConfig,
+    /// URL patterns per content directory, e.g. `blog: /blog/:year/:month/:slug/`
+    #[serde(default)]
+    pub permalinks: BTreeMap<String, String>,
+    /// Ways of grouping pages by front matter terms, e.g. tags or categories
+    #[serde(default)]
+    pub taxonomies: Vec<TaxonomyConfig>,
+    /// Front matter schemas for content directories, keyed by collection name
+    #[serde(default)]
+    pub collections: BTreeMap<String, CollectionConfig>,
+    #[serde(default)]
+    pub check: CheckConfig,
+    #[serde(default)]
+    pub images: ImagesConfig,
+    #[serde(flatten, skip_serializing)]
+    pub unknown: UnknownKeys,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -23,6 +41,29 @@ pub struct SiteConfig {
     pub author: String,
     #[serde(default)]
     pub language: String,
+    /// Free-form values for templates, available as `site.extra`
+    #[serde(default)]
+    pub extra: BTreeMap<String, serde_yaml::Value>,
+    #[serde(flatten, skip_serializing)]
+    pub unknown: UnknownKeys,
+}
+
+/// Keys that matched no field, kept only so they can be reported
+pub type UnknownKeys = BTreeMap<String, serde_yaml::Value>;
+
+/// Warn about each unknown key found in `origin`, which is usually a typo
+/// or a custom value that belongs under `extra`
+pub fn warn_unknown_keys(origin: &str, unknown: &UnknownKeys) {
+    for message in unknown_key_warnings(origin, unknown) {
+        eprintln!("⚠ {}", message);
+    }
+}
+
+fn unknown_key_warnings(origin: &str, unknown: &UnknownKeys) -> Vec<String> {
+    unknown
+        .keys()
+        .map(|key| format!("{}: ignoring unknown key '{}' (custom values go under `extra`)", origin, key))
+        .collect()
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -35,6 +76,22 @@ pub struct BuildConfig {
     pub templates_dir: String,
     #[serde(default = "default_static_dir")]
     pub static_dir: String,
+    #[serde(default = "default_cache_dir")]
+    pub cache_dir: String,
+    /// YAML, JSON, TOML and CSV files exposed to templates as `data`
+    #[serde(default = "default_data_dir")]
+    pub data_dir: String,
+    /// Write static assets under content-hashed names for cache busting.
+    /// Off by default, since templates that link assets by their plain path
+    /// instead of through `asset()` would break.
+    #[serde(default)]
+    pub fingerprint_assets: bool,
+    /// Write `docs/foo.md` as `docs/foo/index.html`, linked as `/docs/foo/`
+    #[serde(default)]
+    pub pretty_urls: bool,
+    /// Where to put a `#` link on each heading, if anywhere
+    #[serde(default)]
+    pub anchor_links: AnchorLinks,
 }
 
 impl Default for BuildConfig {
@@ -44,6 +101,164 @@ fn default() -> Self {
             output_dir: default_output_dir(),
             templates_dir: default_templates_dir(),
             static_dir: default_static_dir(),
+            cache_dir: default_cache_dir(),
+            data_dir: default_data_dir(),
+            fingerprint_assets: false,
+            pretty_urls: false,
+            anchor_links: AnchorLinks::None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AnchorLinks {
+    #[default]
+    None,
+    /// Before the heading text
+    Left,
+    /// After the heading text
+    Right,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaxonomyConfig {
+    /// Front matter key listing a page's terms, also used in URLs
+    pub name: String,
+    /// Write `feed.xml` and `atom.xml` next to every term's listing
+    #[serde(default)]
+    pub feed: bool,
+    /// Split each term's listing into pagers of this many pages
+    #[serde(default)]
+    pub paginate_by: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CollectionConfig {
+    /// Content directory whose pages, at any depth, belong to the collection
+    pub dir: String,
+    #[serde(default)]
+    pub fields: BTreeMap<String, FieldSchema>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FieldSchema {
+    #[serde(rename = "type", default)]
+    pub kind: FieldType,
+    #[serde(default)]
+    pub required: bool,
+    /// Allowed values of an `enum` field
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    /// Anything goes, only presence is checked
+    #[default]
+    Any,
+    String,
+    Number,
+    Boolean,
+    /// `2026-01-31` or a full RFC 3339 timestamp
+    Date,
+    List,
+    /// A string from the schema's `values`
+    Enum,
+    /// Source or site path of another page in the build, or a list of them
+    Reference,
+}
+
+/// Settings for `wokelang-ssg check`. Patterns are globs matched against
+/// whole URLs, e.g. `https://github.com/hyperpolymath/*`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CheckConfig {
+    /// External links that are fine; when set, any other external link fails
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// External links that always fail, even if allowed
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Site paths that are meant to have no incoming links, e.g. `/landing/`
+    #[serde(default)]
+    pub orphans: Vec<String>,
+}
+
+/// Responsive image variants, for `responsive_image()` and content images
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImagesConfig {
+    /// Widths to offer in pixels; an image is never enlarged, and its own
+    /// width is always offered
+    #[serde(default = "default_image_widths")]
+    pub widths: Vec<u32>,
+    /// Formats offered ahead of the original's own, best first
+    #[serde(default = "default_image_formats")]
+    pub formats: Vec<ImageFormat>,
+    /// `sizes` attribute telling browsers how wide the image is displayed
+    #[serde(default = "default_image_sizes")]
+    pub sizes: String,
+    /// Encoder quality from 1 to 100 for JPEG and AVIF; WebP is lossless
+    #[serde(default = "default_image_quality")]
+    pub quality: u8,
+    /// Turn `<img>` tags in content that point at bundled or static images
+    /// into responsive ones
+    #[serde(default)]
+    pub process_content: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Avif,
+    Webp,
+    Jpeg,
+    Png,
+}
+
+impl Default for ImagesConfig {
+    fn default() -> Self {
+        Self {
+            widths: default_image_widths(),
+            formats: default_image_formats(),
+            sizes: default_image_sizes(),
+            quality: default_image_quality(),
+            process_content: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HighlightConfig {
+    #[serde(default)]
+    pub style: HighlightStyle,
+    /// Theme used for inline styles
+    #[serde(default = "default_highlight_theme")]
+    pub theme: String,
+    /// Themes written to `syntax-light.css` and `syntax-dark.css` for classes
+    #[serde(default = "default_light_theme")]
+    pub light_theme: String,
+    #[serde(default = "default_highlight_theme")]
+    pub dark_theme: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightStyle {
+    /// Colours baked into every span as `style` attributes
+    #[default]
+    Inline,
+    /// `hl-*` classes styled by a generated stylesheet
+    Classes,
+}
+
+impl Default for HighlightConfig {
+    fn default() -> Self {
+        Self {
+            style: HighlightStyle::default(),
+            theme: default_highlight_theme(),
+            light_theme: default_light_theme(),
+            dark_theme: default_highlight_theme(),
         }
     }
 }
@@ -64,12 +279,47 @@ fn default_static_dir() -> String {
     "static".to_string()
 }
 
+fn default_cache_dir() -> String {
+    ".cache".to_string()
+}
+
+fn default_data_dir() -> String {
+    "data".to_string()
+}
+
+fn default_image_widths() -> Vec<u32> {
+    vec![480, 960, 1440]
+}
+
+fn default_image_formats() -> Vec<ImageFormat> {
+    vec![ImageFormat::Webp]
+}
+
+fn default_image_sizes() -> String {
+    "100vw".to_string()
+}
+
+fn default_image_quality() -> u8 {
+    80
+}
+
+fn default_highlight_theme() -> String {
+    "base16-ocean.dark".to_string()
+}
+
+fn default_light_theme() -> String {
+    "InspiredGitHub".to_string()
+}
+
 impl Config {
     pub fn load(path: &str) -> Result<Self> {
         let content = fs::read_to_string(path)
             .with_context(|| format!("Failed to read config file: {}", path))?;
         let config: Config = serde_yaml::from_str(&content)
             .with_context(|| format!("Failed to parse config file: {}", path))?;
+
+        warn_unknown_keys(path, &config.unknown);
+        warn_unknown_keys(&format!("{} (site)", path), &config.site.unknown);
         Ok(config)
     }
 
@@ -81,8 +331,47 @@ pub fn default_wokelang() -> Self {
                 description: "A Human-Centered Programming Language".to_string(),
                 author: "Jonathan D.A. Jewell".to_string(),
                 language: "en".to_string(),
+                extra: BTreeMap::new(),
+                unknown: UnknownKeys::new(),
             },
             build: BuildConfig::default(),
+            highlight: HighlightConfig::default(),
+            permalinks: BTreeMap::new(),
+            taxonomies: Vec::new(),
+            collections: BTreeMap::new(),
+            check: CheckConfig::default(),
+            images: ImagesConfig::default(),
+            unknown: UnknownKeys::new(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extra_and_unknown_keys() {
+        let yaml = "site:\n  title: T\n  url: https://example.org\n  description: D\n  \
+                    extra:\n    repo: wokelang/wokelang\n  tittle: typo\nbuidl: {}\n";
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(config.site.extra["repo"], serde_yaml::Value::from("wokelang/wokelang"));
+        assert_eq!(
+            unknown_key_warnings("config.yaml", &config.site.unknown),
+            ["config.yaml: ignoring unknown key 'tittle' (custom values go under `extra`)"]
+        );
+        assert_eq!(config.unknown.keys().collect::<Vec<_>>(), ["buidl"]);
+
+        // Unknown keys are never written back out
+        assert!(!serde_yaml::to_string(&config).unwrap().contains("tittle"));
+    }
+
+    #[test]
+    fn test_assets_keep_their_names_by_default() {
+        let yaml = "site:\n  title: T\n  url: https://example.org\n  description: D\nbuild:\n  output_dir: out\n";
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert!(!config.build.fingerprint_assets);
+        assert!(!Config::default_wokelang().build.fingerprint_assets);
+    }
+}
@@ -5,12 +5,21 @@
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use pulldown_cmark::{html, Options, Parser};
+use gray_matter::Pod;
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
-use syntect::highlighting::ThemeSet;
-use syntect::html::highlighted_html_for_string;
-use syntect::parsing::SyntaxSet;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::config::{warn_unknown_keys, AnchorLinks, UnknownKeys};
+use crate::formats;
+use crate::highlight::Highlighter;
+use crate::shortcodes;
+use crate::templates::Templates;
+use crate::toc::{self, Heading};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FrontMatter {
@@ -21,10 +30,32 @@ pub struct FrontMatter {
     pub date: Option<DateTime<Utc>>,
     #[serde(default)]
     pub draft: bool,
+    /// Pages are left out of the build once this date has passed
+    #[serde(default)]
+    pub expires: Option<DateTime<Utc>>,
     #[serde(default)]
     pub tags: Vec<String>,
     #[serde(default)]
+    pub categories: Vec<String>,
+    /// Terms for custom taxonomies, keyed by taxonomy name
+    #[serde(default)]
+    pub taxonomies: BTreeMap<String, Vec<String>>,
+    #[serde(default = "default_template")]
     pub template: String,
+    /// Position within its section when the section sorts by weight
+    #[serde(default)]
+    pub weight: i64,
+    /// Replaces the file name in the page's URL
+    #[serde(default)]
+    pub slug: Option<String>,
+    /// Publishes the page at exactly this site path, e.g. `/about/`
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Free-form values for templates, available as `page.extra`
+    #[serde(default)]
+    pub extra: BTreeMap<String, serde_yaml::Value>,
+    #[serde(flatten, skip_serializing)]
+    pub unknown: UnknownKeys,
 }
 
 impl Default for FrontMatter {
@@ -34,28 +65,130 @@ fn default() -> Self {
             description: String::new(),
             date: None,
             draft: false,
+            expires: None,
             tags: Vec::new(),
-            template: "page.html".to_string(),
+            categories: Vec::new(),
+            taxonomies: BTreeMap::new(),
+            template: default_template(),
+            weight: 0,
+            slug: None,
+            path: None,
+            extra: BTreeMap::new(),
+            unknown: UnknownKeys::new(),
         }
     }
 }
 
+fn default_template() -> String {
+    "page.html".to_string()
+}
+
+impl FrontMatter {
+    /// Terms the page lists for `taxonomy`; `tags` and `categories` can be
+    /// given at the top level as well as under `taxonomies`
+    pub fn terms<'a>(&'a self, taxonomy: &str) -> impl Iterator<Item = &'a String> {
+        let top_level: &[String] = match taxonomy {
+            "tags" => &self.tags,
+            "categories" => &self.categories,
+            _ => &[],
+        };
+        top_level.iter().chain(self.taxonomies.get(taxonomy).into_iter().flatten())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Page {
     pub front_matter: FrontMatter,
-    pub content: String,
     pub html: String,
+    /// Headings of the rendered body, nested by level
+    pub toc: Vec<Heading>,
+    /// Rendered teaser: everything above `<!-- more -->`, or else the first
+    /// paragraph cut to `SUMMARY_WORDS`
+    pub summary: Option<String>,
+    pub word_count: usize,
+    /// Estimated minutes to read, at `WORDS_PER_MINUTE`
+    pub reading_time: usize,
     pub slug: String,
+    /// Source path relative to the content directory
+    pub relative_path: PathBuf,
+    /// Rendered file, relative to the output directory
+    pub output_path: PathBuf,
+    /// Site-relative URL, e.g. `/docs/getting-started.html` or `/docs/`
+    pub path: String,
+    /// Absolute URL, `site.url` followed by `path`
+    pub permalink: String,
+    /// Preview notice for unpublished pages included via `--drafts`/`--future`
+    pub banner: Option<String>,
+    /// Nearest enclosing section, as an index into the build's sections
+    pub parent: Option<usize>,
+    /// Enclosing sections from the root down to `parent`
+    pub ancestors: Vec<usize>,
+    /// Neighbours in the parent section's page order, as page indices
+    pub previous: Option<usize>,
+    pub next: Option<usize>,
+    /// Files bundled with a directory's `index` page, relative to the directory
+    pub assets: Vec<String>,
+}
+
+/// Where a page stands relative to its publishing window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Publication {
+    Published,
+    Draft,
+    Scheduled(DateTime<Utc>),
+    Expired(DateTime<Utc>),
+}
+
+impl Publication {
+    /// Notice shown on previews of pages that wouldn't normally be published
+    pub fn banner(&self) -> Option<String> {
+        match self {
+            Publication::Published => None,
+            Publication::Draft => Some("Draft".to_string()),
+            Publication::Scheduled(date) => {
+                Some(format!("Scheduled for {}", date.format("%Y-%m-%d %H:%M UTC")))
+            }
+            Publication::Expired(date) => {
+                Some(format!("Expired on {}", date.format("%Y-%m-%d %H:%M UTC")))
+            }
+        }
+    }
 }
 
 impl Page {
-    pub fn from_file(path: &Path) -> Result<Self> {
+    pub fn from_file(
+        path: &Path,
+        highlighter: &Highlighter,
+        templates: &Templates,
+        anchors: AnchorLinks,
+    ) -> Result<Self> {
+        let format = formats::for_path(path)
+            .with_context(|| format!("Unsupported content format: {}", path.display()))?;
         let content = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read file: {}", path.display()))?;
 
-        let (front_matter, markdown) = parse_front_matter(&content)?;
+        let (mut front_matter, source) = parse_front_matter::<FrontMatter>(&content)?;
+        warn_unknown_keys(&path.display().to_string(), &front_matter.unknown);
 
-        let html = markdown_to_html(&markdown)?;
+        // Expanding shortcodes also takes out the summary marker, so it never
+        // shows up as text in formats without HTML comments
+        let expanded = shortcodes::expand(&source, format, highlighter, templates)?;
+        let teaser = expanded.summary_end.map(|end| &expanded.source[..end]);
+
+        let rendered = format.render(&expanded.source, highlighter)?;
+        let html = expanded.restore(&rendered.html);
+        let summary = match teaser {
+            Some(teaser) => {
+                let teaser = expanded.restore(&format.render(teaser, highlighter)?.html);
+                Some(toc::link_headings(&teaser, anchors).0)
+            }
+            None => first_paragraph(&html),
+        };
+        if front_matter.title.is_empty() {
+            if let Some(title) = rendered.title {
+                front_matter.title = title;
+            }
+        }
 
         let slug = path
             .file_stem()
@@ -63,33 +196,119 @@ pub fn from_file(path: &Path) -> Result<Self> {
             .unwrap_or("index")
             .to_string();
 
-        Ok(Self {
+        let (html, toc) = toc::link_headings(&html, anchors);
+        let mut page = Self::new(front_matter, &expanded.text(), html, slug);
+        page.toc = toc;
+        page.summary = summary;
+        Ok(page)
+    }
+
+    /// A page that hasn't been placed in the site yet, with words counted
+    /// in `body`, its source with shortcodes expanded
+    pub fn new(front_matter: FrontMatter, body: &str, html: String, slug: String) -> Self {
+        let word_count = count_words(body);
+        Self {
             front_matter,
-            content: markdown,
             html,
+            toc: Vec::new(),
+            summary: None,
+            word_count,
+            reading_time: word_count.div_ceil(WORDS_PER_MINUTE),
             slug,
-        })
+            relative_path: PathBuf::new(),
+            output_path: PathBuf::new(),
+            path: String::new(),
+            permalink: String::new(),
+            banner: None,
+            parent: None,
+            ancestors: Vec::new(),
+            previous: None,
+            next: None,
+            assets: Vec::new(),
+        }
+    }
+
+    pub fn publication(&self, now: DateTime<Utc>) -> Publication {
+        let fm = &self.front_matter;
+        if fm.draft {
+            return Publication::Draft;
+        }
+        match (fm.date, fm.expires) {
+            (Some(date), _) if date > now => Publication::Scheduled(date),
+            (_, Some(expires)) if expires <= now => Publication::Expired(expires),
+            _ => Publication::Published,
+        }
     }
 }
 
-fn parse_front_matter(content: &str) -> Result<(FrontMatter, String)> {
-    if let Some(content) = content.strip_prefix("---\n") {
-        if let Some(end) = content.find("\n---\n") {
-            let yaml = &content[..end];
-            let markdown = &content[end + 5..];
+/// Split a file into its front matter and body.
+///
+/// The front matter format is picked from how the file starts: `---` for
+/// YAML, `+++` for TOML (as used by Hugo and Zola) or a bare JSON object.
+/// A UTF-8 byte order mark is ignored and CRLF line endings are accepted.
+/// Files without front matter get `T::default()`.
+pub fn parse_front_matter<T: DeserializeOwned + Default>(content: &str) -> Result<(T, String)> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let content = content.replace("\r\n", "\n");
 
-            let front_matter: FrontMatter = serde_yaml::from_str(yaml)
-                .with_context(|| "Failed to parse front matter")?;
+    if let Some((yaml, markdown)) = split_delimited(&content, "---") {
+        let front_matter: T = serde_yaml::from_str(yaml)
+            .with_context(|| "Failed to parse front matter")?;
+        return Ok((front_matter, markdown.to_string()));
+    }
 
-            return Ok((front_matter, markdown.to_string()));
-        }
+    if let Some((toml, markdown)) = split_delimited(&content, "+++") {
+        let value: toml::Value = toml::from_str(toml).with_context(|| "Failed to parse TOML front matter")?;
+        // Goes through gray_matter's `Pod`, which turns TOML datetimes into
+        // the strings our date fields expect
+        let front_matter: T = Pod::from(value)
+            .deserialize()
+            .with_context(|| "Failed to parse TOML front matter")?;
+        return Ok((front_matter, markdown.to_string()));
+    }
+
+    if starts_with_json_object(&content) {
+        let mut stream = serde_json::Deserializer::from_str(&content).into_iter::<serde_json::Value>();
+        let value = stream
+            .next()
+            .transpose()
+            .with_context(|| "Failed to parse JSON front matter")?
+            .unwrap_or_default();
+        let markdown = &content[stream.byte_offset()..];
+        let front_matter: T = serde_json::from_value(value).with_context(|| "Failed to parse JSON front matter")?;
+        return Ok((front_matter, markdown.strip_prefix('\n').unwrap_or(markdown).to_string()));
     }
 
     // No front matter found
-    Ok((FrontMatter::default(), content.to_string()))
+    Ok((T::default(), content))
+}
+
+/// Text between an opening `delimiter` line and the next closing one, and
+/// everything after it
+fn split_delimited<'a>(content: &'a str, delimiter: &str) -> Option<(&'a str, &'a str)> {
+    let rest = content.strip_prefix(delimiter)?;
+    let rest = rest.trim_start_matches([' ', '\t']).strip_prefix('\n')?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == delimiter {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Whether the file opens with a JSON object, as opposed to Markdown that
+/// happens to start with a brace, like a `{{ shortcode() }}`
+fn starts_with_json_object(content: &str) -> bool {
+    content
+        .strip_prefix('{')
+        .and_then(|rest| rest.trim_start().chars().next())
+        .is_some_and(|c| c == '"' || c == '}')
 }
 
-fn markdown_to_html(markdown: &str) -> Result<String> {
+pub fn markdown_to_html(markdown: &str, highlighter: &Highlighter) -> Result<String> {
     let mut options = Options::empty();
     options.insert(Options::ENABLE_STRIKETHROUGH);
     options.insert(Options::ENABLE_TABLES);
@@ -97,31 +316,112 @@ fn markdown_to_html(markdown: &str) -> Result<String> {
     options.insert(Options::ENABLE_TASKLISTS);
     options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
 
-    let parser = Parser::new_ext(markdown, options);
+    let mut events = Vec::new();
+    let mut code_block: Option<(String, String)> = None;
+
+    // Fenced blocks with a language are replaced by highlighted HTML; the
+    // rest of the stream passes through untouched
+    for event in Parser::new_ext(markdown, options) {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) if !info.is_empty() => {
+                code_block = Some((code_language(&info).to_string(), String::new()));
+            }
+            Event::Text(text) if code_block.is_some() => {
+                if let Some((_, code)) = code_block.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) if code_block.is_some() => {
+                if let Some((language, code)) = code_block.take() {
+                    let html = highlighter.highlight(&code, &language)?;
+                    events.push(Event::Html(CowStr::from(html)));
+                }
+            }
+            event => events.push(event),
+        }
+    }
+
     let mut html_output = String::new();
-    html::push_html(&mut html_output, parser);
+    html::push_html(&mut html_output, events.into_iter());
 
     Ok(html_output)
 }
 
-pub fn highlight_code(code: &str, language: &str) -> Result<String> {
-    let syntax_set = SyntaxSet::load_defaults_newlines();
-    let theme_set = ThemeSet::load_defaults();
+/// Language token of a fence info string such as `woke` or `rust,ignore`
+fn code_language(info: &str) -> &str {
+    info.split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+        .next()
+        .unwrap_or_default()
+}
+
+/// Splits a page into its summary and the rest
+pub const SUMMARY_MARKER: &str = "<!-- more -->";
+
+/// Longest automatic summary, in words
+pub const SUMMARY_WORDS: usize = 50;
+
+/// Reading speed used for `reading_time`
+pub const WORDS_PER_MINUTE: usize = 200;
+
+/// Words in a page source, leaving out tokens that are only markup such as
+/// `#`, `-` or code fences
+pub fn count_words(source: &str) -> usize {
+    source
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+/// The first paragraph of rendered HTML, as plain text cut to
+/// `SUMMARY_WORDS` when it runs longer
+fn first_paragraph(html: &str) -> Option<String> {
+    static PARAGRAPH: OnceLock<Regex> = OnceLock::new();
+    let re = PARAGRAPH.get_or_init(|| Regex::new(r"(?s)<p(?:\s[^>]*)?>(.*?)</p>").unwrap());
+
+    let paragraph = re.captures(html)?;
+    let text = html_to_text(&paragraph[1]);
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.len() <= SUMMARY_WORDS {
+        Some(paragraph[0].to_string())
+    } else {
+        Some(format!("<p>{}…</p>", formats::escape(&words[..SUMMARY_WORDS].join(" "))))
+    }
+}
 
-    let syntax = syntax_set
-        .find_syntax_by_extension(language)
-        .or_else(|| syntax_set.find_syntax_by_name(language))
-        .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+/// Text of an HTML fragment: tags dropped, common entities decoded
+pub fn html_to_text(html: &str) -> String {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    let re = TAG.get_or_init(|| Regex::new(r"<[^>]*>").unwrap());
 
-    let html = highlighted_html_for_string(code, &syntax_set, syntax, &theme_set.themes["base16-ocean.dark"])
-        .with_context(|| "Failed to highlight code")?;
+    re.replace_all(html, "")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
 
-    Ok(html)
+/// URL-safe form of `text`: lowercase letters and digits, with every run of
+/// anything else collapsed to a single `-`
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(slug.trim_end_matches('-').len());
+    slug
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{highlighter, TestSite};
 
     #[test]
     fn test_parse_front_matter() {
@@ -133,17 +433,163 @@ fn test_parse_front_matter() {
 
 This is content."#;
 
-        let (fm, md) = parse_front_matter(content).unwrap();
+        let (fm, md) = parse_front_matter::<FrontMatter>(content).unwrap();
         assert_eq!(fm.title, "Test Page");
         assert_eq!(fm.description, "A test page");
         assert!(md.contains("# Hello World"));
     }
 
+    #[test]
+    fn test_toml_and_json_front_matter() {
+        let toml = "+++\ntitle = \"Toml\"\ndate = 2026-03-01T10:00:00Z\ntags = [\"a\"]\n\n[extra]\nhero = \"h.png\"\n+++\n# Body\n";
+        let (fm, body) = parse_front_matter::<FrontMatter>(toml).unwrap();
+        assert_eq!(fm.title, "Toml");
+        assert_eq!(fm.date.unwrap().to_rfc3339(), "2026-03-01T10:00:00+00:00");
+        assert_eq!(fm.tags, ["a"]);
+        assert_eq!(fm.extra["hero"], serde_yaml::Value::from("h.png"));
+        assert_eq!(body, "# Body\n");
+
+        let json = "{\n  \"title\": \"Json\",\n  \"weight\": 3\n}\n# Body\n";
+        let (fm, body) = parse_front_matter::<FrontMatter>(json).unwrap();
+        assert_eq!((fm.title.as_str(), fm.weight), ("Json", 3));
+        assert_eq!(body, "# Body\n");
+
+        // Braces that open a shortcode rather than an object are just Markdown
+        let (fm, body) = parse_front_matter::<FrontMatter>("{{ youtube(id=\"x\") }}\n").unwrap();
+        assert_eq!(fm.title, "");
+        assert_eq!(body, "{{ youtube(id=\"x\") }}\n");
+
+        assert!(parse_front_matter::<FrontMatter>("+++\ntitle = \n+++\n").is_err());
+    }
+
+    #[test]
+    fn test_bom_and_crlf_front_matter() {
+        let content = "\u{feff}---\r\ntitle: Windows\r\n---\r\nLine one\r\nLine two\r\n";
+        let (fm, body) = parse_front_matter::<FrontMatter>(content).unwrap();
+        assert_eq!(fm.title, "Windows");
+        assert_eq!(body, "Line one\nLine two\n");
+    }
+
+    #[test]
+    fn test_extra_front_matter() {
+        let content = "---\ntitle: Hi\ntittle: typo\nextra:\n  hero_image: hero.png\n  toc: false\n---\nBody";
+        let (fm, _) = parse_front_matter::<FrontMatter>(content).unwrap();
+
+        assert_eq!(fm.extra["hero_image"], serde_yaml::Value::from("hero.png"));
+        assert_eq!(fm.extra["toc"], serde_yaml::Value::from(false));
+        assert_eq!(fm.unknown.keys().collect::<Vec<_>>(), ["tittle"]);
+    }
+
+    #[test]
+    fn test_publication() {
+        let now = "2026-06-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let page = |yaml: &str| {
+            Page::new(serde_yaml::from_str(yaml).unwrap(), "", String::new(), "test".to_string())
+        };
+
+        assert_eq!(page("title: A").publication(now), Publication::Published);
+        assert_eq!(page("title: A\ndraft: true").publication(now), Publication::Draft);
+        assert!(matches!(
+            page("title: A\ndate: 2026-07-01T00:00:00Z").publication(now),
+            Publication::Scheduled(_)
+        ));
+        assert!(matches!(
+            page("title: A\nexpires: 2026-05-01T00:00:00Z").publication(now),
+            Publication::Expired(_)
+        ));
+        assert_eq!(
+            page("title: A\ndate: 2026-05-01T00:00:00Z\nexpires: 2026-07-01T00:00:00Z").publication(now),
+            Publication::Published
+        );
+    }
+
     #[test]
     fn test_markdown_to_html() {
         let md = "# Hello\n\nThis is **bold**.";
-        let html = markdown_to_html(md).unwrap();
+        let html = markdown_to_html(md, highlighter()).unwrap();
         assert!(html.contains("<h1>"));
         assert!(html.contains("<strong>bold</strong>"));
     }
+
+    #[test]
+    fn test_fenced_code_is_highlighted() {
+        let md = "```woke\nto main() {\n    print(\"hi\");\n}\n```\n\n```\nplain\n```\n";
+        let html = markdown_to_html(md, highlighter()).unwrap();
+        assert!(html.contains("<pre style="));
+        assert!(!html.contains("language-woke"));
+        assert!(html.contains("<pre><code>plain"));
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("WokeLang 0.3"), "wokelang-0-3");
+        assert_eq!(slugify("  Consent & Safety! "), "consent-safety");
+        assert_eq!(slugify("Café"), "café");
+        assert_eq!(slugify("--"), "");
+    }
+
+    #[test]
+    fn test_pages_in_every_format() {
+        let site = TestSite::new();
+        site.write("content/policy.djot", "---\ntitle: Policy\n---\nUse *Rust* for [tools](@/guide.adoc).\n");
+        site.write("content/guide.adoc", "= Getting Started\n:lang: WokeLang\n\nInstall {lang} _today_.\n");
+
+        let policy = site.page("policy.djot").unwrap();
+        assert_eq!(policy.html, "<p>Use <strong>Rust</strong> for <a href=\"@/guide.adoc\">tools</a>.</p>\n");
+
+        let guide = site.page("guide.adoc").unwrap();
+        assert_eq!(guide.front_matter.title, "Getting Started");
+        assert_eq!(guide.html, "<p>Install WokeLang <em>today</em>.</p>\n");
+    }
+
+    #[test]
+    fn test_summary_word_count_and_reading_time() {
+        let site = TestSite::new();
+        let read = |file: &str, source: &str| {
+            site.write(&format!("content/{}", file), source);
+            site.page(file).unwrap()
+        };
+
+        let page = read("marked.md", "---\ntitle: A\n---\n## Intro\n\nFirst *part*.\n\n<!-- more -->\n\nSecond part.\n");
+        assert_eq!(page.summary.as_deref(), Some("<h2 id=\"intro\">Intro</h2>\n<p>First <em>part</em>.</p>\n"));
+        assert!(page.html.contains("<p>Second part.</p>"));
+        assert_eq!(page.word_count, 5);
+        assert_eq!(page.reading_time, 1);
+
+        let page = read("marked.djot", "Teaser.\n\n<!-- more -->\n\nRest.\n");
+        assert_eq!(page.summary.as_deref(), Some("<p>Teaser.</p>\n"));
+        assert_eq!(page.html, "<p>Teaser.</p>\n<p>Rest.</p>\n");
+
+        let long = vec!["word"; 450].join(" ");
+        let page = read("long.md", &format!("# Title\n\nShort & sweet.\n\n{}\n", long));
+        assert_eq!(page.summary.as_deref(), Some("<p>Short &amp; sweet.</p>"));
+        assert_eq!(page.word_count, 453);
+        assert_eq!(page.reading_time, 3);
+
+        let page = read("run-on.md", &format!("{}\n", long));
+        let summary = page.summary.unwrap();
+        assert_eq!(summary.split_whitespace().count(), SUMMARY_WORDS);
+        assert!(summary.ends_with("word…</p>"));
+
+        assert_eq!(read("empty.md", "").reading_time, 0);
+    }
+
+    #[test]
+    fn test_summary_marker_in_code_and_shortcode_words() {
+        let site = TestSite::new();
+        site.write("templates/shortcodes/note.html", "<aside>{{ body | safe }}</aside>");
+        site.write(
+            "content/docs.md",
+            "---\ntitle: A\n---\nPut `<!-- more -->` on its own line:\n\n```\n<!-- more -->\n```\n\n\
+             {% note(kind=\"tip\", level=1) %}Keep it short{% end %}\n\n<!-- more -->\n\nRest.\n",
+        );
+        let page = site.page("docs.md").unwrap();
+
+        let summary = page.summary.unwrap();
+        assert!(summary.contains("<pre><code>&lt;!-- more --&gt;\n</code></pre>"), "{}", summary);
+        assert!(summary.trim_end().ends_with("<aside><p>Keep it short</p></aside>"), "{}", summary);
+        assert!(!page.html.contains("<!-- more -->"));
+        // The note's three words count, its tag and arguments don't
+        assert_eq!(page.word_count, 11);
+    }
 }
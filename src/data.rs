@@ -0,0 +1,309 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Data files
+//!
+//! YAML, JSON, TOML and CSV files under the data directory are loaded once
+//! per build into the `data` namespace of every template, nested by path:
+//! `data/releases.yaml` is `data.releases` and `data/stdlib/core.json` is
+//! `data.stdlib.core`. CSV files become a list of rows keyed by the header.
+//! Templates and shortcodes can also read a file of their own with
+//! `load_data(path=...)`. When the data directory changes, the build cache
+//! re-renders only pages whose templates use `data` or `load_data`, and of
+//! the content files it watches only those `load_data` read last time.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use walkdir::WalkDir;
+
+use crate::cache;
+
+/// Everything under `dir`, as nested objects keyed by directory and file stem
+pub fn load_dir(dir: &Path) -> Result<Value> {
+    let mut data = Map::new();
+    if !dir.exists() {
+        return Ok(Value::Object(data));
+    }
+
+    for entry in WalkDir::new(dir).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() || !is_data_file(entry.path()) {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+        let value = load_file(entry.path())?;
+
+        let mut keys: Vec<String> = relative
+            .parent()
+            .into_iter()
+            .flat_map(|p| p.components())
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        keys.push(relative.file_stem().unwrap_or_default().to_string_lossy().into_owned());
+
+        let (name, dirs) = keys.split_last().unwrap();
+        let mut table = &mut data;
+        for key in dirs {
+            let slot = table.entry(key.clone()).or_insert_with(|| Value::Object(Map::new()));
+            table = match slot {
+                Value::Object(table) => table,
+                _ => bail!("{}: data.{} is already defined by a file", entry.path().display(), key),
+            };
+        }
+        if table.contains_key(name) {
+            bail!(
+                "{}: data.{} is already defined by another file or directory",
+                entry.path().display(),
+                keys.join(".")
+            );
+        }
+        table.insert(name.clone(), value);
+    }
+    Ok(Value::Object(data))
+}
+
+/// Parse one data file, picking the format from its extension
+pub fn load_file(path: &Path) -> Result<Value> {
+    let text = fs::read_to_string(path).with_context(|| format!("Failed to read data file {}", path.display()))?;
+    let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+
+    let value = match extension.to_ascii_lowercase().as_str() {
+        "yaml" | "yml" => serde_yaml::from_str(text).map_err(anyhow::Error::from),
+        "json" => serde_json::from_str(text).map_err(anyhow::Error::from),
+        "toml" => toml::from_str(text).map_err(anyhow::Error::from),
+        "csv" => parse_csv(text),
+        _ => bail!("{}: data files must be YAML, JSON, TOML or CSV", path.display()),
+    };
+    value.with_context(|| format!("Failed to parse data file {}", path.display()))
+}
+
+/// Hash of every data file under `dir`, so pages that read one are
+/// re-rendered when it changes
+pub fn sources_hash(dir: &Path) -> Result<String> {
+    let mut hashes = String::new();
+    if dir.exists() {
+        for entry in WalkDir::new(dir).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+            if entry.file_type().is_file() && is_data_file(entry.path()) {
+                hashes.push_str(&entry.path().to_string_lossy());
+                hashes.push_str(&cache::hash_file(entry.path())?);
+            }
+        }
+    }
+    Ok(cache::hash_bytes(hashes.as_bytes()))
+}
+
+/// Hash of the content files in `files`, given relative to `content_dir`.
+/// A missing file hashes differently from any contents, so deleting one
+/// counts as a change.
+pub fn content_files_hash(content_dir: &Path, files: &BTreeSet<String>) -> Result<String> {
+    let mut hashes = String::new();
+    for file in files {
+        let path = content_dir.join(file);
+        hashes.push_str(file);
+        if path.is_file() {
+            hashes.push_str(&cache::hash_file(&path)?);
+        }
+        hashes.push('\n');
+    }
+    Ok(cache::hash_bytes(hashes.as_bytes()))
+}
+
+fn is_data_file(path: &Path) -> bool {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    matches!(extension.to_ascii_lowercase().as_str(), "yaml" | "yml" | "json" | "toml" | "csv")
+}
+
+/// Rows as objects keyed by the header row, every value a string
+fn parse_csv(text: &str) -> Result<Value> {
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    let headers = reader.headers()?.clone();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let row: Map<String, Value> = headers
+            .iter()
+            .zip(record.iter())
+            .map(|(key, value)| (key.to_string(), Value::String(value.to_string())))
+            .collect();
+        rows.push(Value::Object(row));
+    }
+    Ok(Value::Array(rows))
+}
+
+/// Tera function `load_data(path="stdlib/extra.csv")`. Paths are relative to
+/// the data directory, or to the content directory with an `@/` prefix for
+/// files kept next to a page, and can't leave either.
+///
+/// Clones share what has been loaded, so the builder can keep one to ask
+/// which content files were read.
+#[derive(Clone)]
+pub struct LoadData {
+    data_dir: PathBuf,
+    content_dir: PathBuf,
+    /// Files already parsed this build, since many pages may read the same one
+    loaded: Arc<Mutex<HashMap<PathBuf, Arc<Value>>>>,
+    /// Content files read this build, relative to the content directory
+    content_files: Arc<Mutex<BTreeSet<String>>>,
+}
+
+impl LoadData {
+    pub fn new(data_dir: &Path, content_dir: &Path) -> Self {
+        Self {
+            data_dir: data_dir.to_path_buf(),
+            content_dir: content_dir.to_path_buf(),
+            loaded: Arc::default(),
+            content_files: Arc::default(),
+        }
+    }
+
+    /// Content files read so far, relative to the content directory
+    pub fn content_files(&self) -> BTreeSet<String> {
+        self.content_files.lock().unwrap().clone()
+    }
+
+    fn load(&self, path: &str) -> Result<Arc<Value>> {
+        let file = match path.strip_prefix("@/") {
+            Some(relative) => {
+                let relative = inside(relative, path)?;
+                self.content_files.lock().unwrap().insert(cache::path_key(&relative));
+                self.content_dir.join(relative)
+            }
+            None => self.data_dir.join(inside(path.trim_start_matches('/'), path)?),
+        };
+        if let Some(value) = self.loaded.lock().unwrap().get(&file) {
+            return Ok(Arc::clone(value));
+        }
+        let value = Arc::new(load_file(&file)?);
+        self.loaded.lock().unwrap().insert(file, Arc::clone(&value));
+        Ok(value)
+    }
+}
+
+/// `relative` as a path that stays inside the directory it's joined to
+fn inside(relative: &str, path: &str) -> Result<PathBuf> {
+    let relative = Path::new(relative);
+    if !relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        bail!("'{}' points outside its directory", path);
+    }
+    Ok(relative.to_path_buf())
+}
+
+impl tera::Function for LoadData {
+    fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+        let path = args
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| tera::Error::msg("load_data() requires a string `path` argument"))?;
+        let value = self
+            .load(path)
+            .map_err(|e| tera::Error::msg(format!("load_data(): {:#}", e)))?;
+        Ok(Value::clone(&value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{write, TestSite};
+    use serde_json::json;
+
+    #[test]
+    fn test_load_dir_nests_every_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "releases.yaml", "- version: 0.2.0\n  codename: Gratitude\n");
+        write(root, "team/core.json", r#"{"lead": "Jonathan"}"#);
+        write(root, "team/reviewers.toml", "names = [\"A\", \"B\"]\n");
+        write(root, "stdlib/index.csv", "module,summary\r\nconsent,\"Ask, then act\"\nio,Files\n");
+        write(root, "notes.txt", "ignored");
+
+        assert_eq!(
+            load_dir(root).unwrap(),
+            json!({
+                "releases": [{ "version": "0.2.0", "codename": "Gratitude" }],
+                "team": { "core": { "lead": "Jonathan" }, "reviewers": { "names": ["A", "B"] } },
+                "stdlib": { "index": [
+                    { "module": "consent", "summary": "Ask, then act" },
+                    { "module": "io", "summary": "Files" },
+                ] },
+            })
+        );
+        assert_eq!(load_dir(&root.join("missing")).unwrap(), json!({}));
+    }
+
+    #[test]
+    fn test_clashing_and_broken_files_fail() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "releases.json", "[]");
+        write(root, "releases.yaml", "[]");
+        let err = format!("{:#}", load_dir(root).unwrap_err());
+        assert!(err.contains("data.releases is already defined"), "{}", err);
+
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "broken.toml", "= nope");
+        let err = format!("{:#}", load_dir(dir.path()).unwrap_err());
+        assert!(err.contains("Failed to parse data file"), "{}", err);
+    }
+
+    #[test]
+    fn test_load_data_stays_inside_its_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "data/releases.json", "[1]");
+        write(root, "content/stdlib/modules.csv", "module\nio\n");
+        write(root, "secret.json", "{}");
+        let loader = LoadData::new(&root.join("data"), &root.join("content"));
+
+        assert_eq!(*loader.load("releases.json").unwrap(), json!([1]));
+        assert_eq!(*loader.clone().load("@/stdlib/modules.csv").unwrap(), json!([{ "module": "io" }]));
+        for path in ["../secret.json", "@/../secret.json", "@/stdlib/../../secret.json", "@//etc/passwd"] {
+            let err = loader.load(path).unwrap_err().to_string();
+            assert_eq!(err, format!("'{}' points outside its directory", path));
+        }
+        assert_eq!(loader.content_files(), BTreeSet::from(["stdlib/modules.csv".to_string()]));
+    }
+
+    #[test]
+    fn test_shortcodes_read_data_and_load_data() {
+        let site = TestSite::new();
+        site.write("data/releases.yaml", "- version: 0.1.0\n- version: 0.2.0\n");
+        site.write("templates/shortcodes/latest.html", "{{ data.releases | last | get(key=\"version\") }}");
+        site.write(
+            "templates/shortcodes/modules.html",
+            "{% set rows = load_data(path=\"@/stdlib/modules.csv\") %}\
+             {% for row in rows %}{{ row.module }};{% endfor %}",
+        );
+        site.write("templates/shortcodes/secret.html", "{{ load_data(path=\"../secret.json\") }}");
+        site.write("content/stdlib/modules.csv", "module\nconsent\nio\n");
+        site.write("content/stdlib/index.md", "---\ntitle: Stdlib\n---\nLatest: {{ latest() }}, {{ modules() }}\n");
+        site.write("content/leak.md", "---\ntitle: Leak\n---\n{{ secret() }}\n");
+
+        assert_eq!(site.page("stdlib/index.md").unwrap().html, "<p>Latest: 0.2.0, consent;io;</p>\n");
+        let err = format!("{:#}", site.page("leak.md").unwrap_err());
+        assert!(err.contains("'../secret.json' points outside its directory"), "{}", err);
+    }
+
+    #[test]
+    fn test_content_files_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "modules.csv", "module\nio\n");
+        write(root, "unread.json", "{}");
+        let files = BTreeSet::from(["modules.csv".to_string()]);
+        let hash = || content_files_hash(root, &files).unwrap();
+
+        let original = hash();
+        write(root, "unread.json", "[]");
+        assert_eq!(hash(), original);
+        write(root, "modules.csv", "module\n");
+        let edited = hash();
+        assert_ne!(edited, original);
+        fs::remove_file(root.join("modules.csv")).unwrap();
+        assert_ne!(hash(), edited);
+    }
+}
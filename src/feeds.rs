@@ -4,27 +4,42 @@
 //! RSS and Atom feed generation
 
 use anyhow::Result;
-use atom_syndication::{Entry, Feed, Link, Person};
+use atom_syndication::{Entry, Feed, Link, Person, Text};
 use chrono::Utc;
-use rss::{Channel, ChannelBuilder, Item, ItemBuilder};
+use rss::{ChannelBuilder, ItemBuilder};
 use std::fs;
 use std::path::Path;
 
 use crate::config::Config;
 use crate::content::Page;
 
-pub fn generate_rss(pages: &[&Page], config: &Config, output_path: &Path) -> Result<()> {
-    let mut items = Vec::new();
+/// Channel-level details shared by the RSS and Atom versions of a feed
+pub struct FeedChannel {
+    pub title: String,
+    pub link: String,
+    pub description: String,
+}
 
-    for page in pages {
-        if page.front_matter.draft {
-            continue;
+impl FeedChannel {
+    /// The site-wide feed
+    pub fn site(config: &Config) -> Self {
+        Self {
+            title: config.site.title.clone(),
+            link: config.site.url.clone(),
+            description: config.site.description.clone(),
         }
+    }
+}
 
+/// Write `feed.xml` into `output_dir`
+pub fn generate_rss(pages: &[&Page], channel: &FeedChannel, config: &Config, output_dir: &Path) -> Result<()> {
+    let mut items = Vec::new();
+
+    for page in pages {
         let item = ItemBuilder::default()
             .title(Some(page.front_matter.title.clone()))
-            .description(Some(page.front_matter.description.clone()))
-            .link(Some(format!("{}/{}", config.site.url, page.slug)))
+            .description(item_description(page).map(|text| text.value))
+            .link(Some(page.permalink.clone()))
             .pub_date(page.front_matter.date.map(|d| d.to_rfc2822()))
             .build();
 
@@ -32,37 +47,34 @@ pub fn generate_rss(pages: &[&Page], config: &Config, output_path: &Path) -> Res
     }
 
     let channel = ChannelBuilder::default()
-        .title(&config.site.title)
-        .link(&config.site.url)
-        .description(&config.site.description)
+        .title(&channel.title)
+        .link(&channel.link)
+        .description(&channel.description)
         .language(Some(config.site.language.clone()))
         .items(items)
         .build();
 
-    let rss_path = output_path.join("feed.xml");
+    let rss_path = output_dir.join("feed.xml");
     let rss_string = channel.to_string();
     fs::write(rss_path, rss_string)?;
 
     Ok(())
 }
 
-pub fn generate_atom(pages: &[&Page], config: &Config, output_path: &Path) -> Result<()> {
+/// Write `atom.xml` into `output_dir`
+pub fn generate_atom(pages: &[&Page], channel: &FeedChannel, config: &Config, output_dir: &Path) -> Result<()> {
     let mut entries = Vec::new();
 
     for page in pages {
-        if page.front_matter.draft {
-            continue;
-        }
-
         let entry = Entry {
             title: page.front_matter.title.clone().into(),
-            id: format!("{}/{}", config.site.url, page.slug),
-            updated: page.front_matter.date.unwrap_or_else(Utc::now),
+            id: page.permalink.clone(),
+            updated: page.front_matter.date.unwrap_or_else(Utc::now).fixed_offset(),
             links: vec![Link {
-                href: format!("{}/{}", config.site.url, page.slug),
+                href: page.permalink.clone(),
                 ..Default::default()
             }],
-            summary: Some(page.front_matter.description.clone().into()),
+            summary: item_description(page),
             ..Default::default()
         };
 
@@ -70,24 +82,63 @@ pub fn generate_atom(pages: &[&Page], config: &Config, output_path: &Path) -> Re
     }
 
     let feed = Feed {
-        title: config.site.title.clone().into(),
-        id: config.site.url.clone(),
-        updated: Utc::now(),
+        title: channel.title.clone().into(),
+        id: channel.link.clone(),
+        updated: Utc::now().fixed_offset(),
         authors: vec![Person {
             name: config.site.author.clone(),
             ..Default::default()
         }],
         links: vec![Link {
-            href: config.site.url.clone(),
+            href: channel.link.clone(),
             ..Default::default()
         }],
+        subtitle: Some(channel.description.clone().into()),
         entries,
         ..Default::default()
     };
 
-    let atom_path = output_path.join("atom.xml");
+    let atom_path = output_dir.join("atom.xml");
     let atom_string = feed.to_string();
     fs::write(atom_path, atom_string)?;
 
     Ok(())
 }
+
+/// What a feed entry says about a page: its description, or its rendered
+/// summary when the description is empty
+fn item_description(page: &Page) -> Option<Text> {
+    if !page.front_matter.description.is_empty() {
+        return Some(Text::plain(page.front_matter.description.clone()));
+    }
+    page.summary.clone().map(Text::html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_show_the_description_or_else_the_summary() {
+        let page = |front_matter: &str, summary: &str| {
+            let mut page = Page::new(serde_yaml::from_str(front_matter).unwrap(), "", String::new(), String::new());
+            page.summary = Some(summary.to_string());
+            page
+        };
+        let a = page("title: A", "<p>Teaser for A.</p>\n");
+        let b = page("title: B\ndescription: About B", "<p>First paragraph of B.</p>");
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::default_wokelang();
+        let channel = FeedChannel::site(&config);
+        generate_rss(&[&a, &b], &channel, &config, dir.path()).unwrap();
+        generate_atom(&[&a, &b], &channel, &config, dir.path()).unwrap();
+
+        let rss = fs::read_to_string(dir.path().join("feed.xml")).unwrap();
+        assert!(rss.contains("<description><![CDATA[<p>Teaser for A.</p>\n]]></description>"), "{}", rss);
+        assert!(rss.contains("<description><![CDATA[About B]]></description>"), "{}", rss);
+        let atom = fs::read_to_string(dir.path().join("atom.xml")).unwrap();
+        assert!(atom.contains("<summary type=\"html\">&lt;p&gt;Teaser for A.&lt;/p&gt;\n</summary>"), "{}", atom);
+        assert!(atom.contains("<summary>About B</summary>"), "{}", atom);
+    }
+}
@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Content formats
+//!
+//! Pages can be written in Markdown, Djot or AsciiDoc. Each markup language
+//! implements `ContentFormat`, and a page's file extension decides which one
+//! renders it. Front matter works the same way in every format.
+
+mod asciidoc;
+mod djot;
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::content::markdown_to_html;
+use crate::highlight::Highlighter;
+
+pub use asciidoc::AsciiDoc;
+pub use djot::Djot;
+
+/// A rendered document body
+pub struct Rendered {
+    pub html: String,
+    /// Title given by the document itself, such as an AsciiDoc `= Title`
+    /// header, used when the front matter has none
+    pub title: Option<String>,
+}
+
+pub trait ContentFormat: Sync {
+    /// File extensions handled by the format, without the dot
+    fn extensions(&self) -> &'static [&'static str];
+
+    fn render(&self, source: &str, highlighter: &Highlighter) -> Result<Rendered>;
+}
+
+pub struct Markdown;
+
+impl ContentFormat for Markdown {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["md", "markdown"]
+    }
+
+    fn render(&self, source: &str, highlighter: &Highlighter) -> Result<Rendered> {
+        Ok(Rendered {
+            html: markdown_to_html(source, highlighter)?,
+            title: None,
+        })
+    }
+}
+
+const FORMATS: &[&dyn ContentFormat] = &[&Markdown, &Djot, &AsciiDoc];
+
+/// Format for the file at `path`, or `None` if it isn't content
+pub fn for_path(path: &Path) -> Option<&'static dyn ContentFormat> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    FORMATS
+        .iter()
+        .copied()
+        .find(|format| format.extensions().contains(&extension.as_str()))
+}
+
+/// Decode the entities `escape` and templates write into attribute values
+pub fn unescape(value: &str) -> String {
+    value
+        .replace("&#x2F;", "/")
+        .replace("&#47;", "/")
+        .replace("&#x27;", "'")
+        .replace("&#39;", "'")
+        .replace("&quot;", "\"")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Escape text for use in HTML content and attribute values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A code block, highlighted when it names a language
+fn code_block(code: &str, language: Option<&str>, highlighter: &Highlighter) -> Result<String> {
+    match language.filter(|l| !l.is_empty()) {
+        Some(language) => highlighter.highlight(code, language),
+        None => Ok(format!("<pre><code>{}</code></pre>\n", escape(code))),
+    }
+}
+
+/// ` id="..." class="..."` for an element, empty when there's neither
+fn attributes(id: Option<&str>, classes: &[String]) -> String {
+    let mut html = String::new();
+    if let Some(id) = id {
+        html.push_str(&format!(" id=\"{}\"", escape(id)));
+    }
+    if !classes.is_empty() {
+        html.push_str(&format!(" class=\"{}\"", escape(&classes.join(" "))));
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_for_path() {
+        assert!(for_path(Path::new("docs/intro.md")).is_some());
+        assert!(for_path(Path::new("README.adoc")).is_some());
+        assert!(for_path(Path::new("AI.djot")).is_some());
+        assert!(for_path(Path::new("logo.png")).is_none());
+        assert!(for_path(Path::new("Makefile")).is_none());
+    }
+}
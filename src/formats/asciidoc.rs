@@ -0,0 +1,1185 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! AsciiDoc
+//!
+//! A subset of AsciiDoc, enough for the project documentation we publish:
+//! the document header and attributes, section titles, paragraphs and
+//! admonitions, the common delimited blocks (listing, literal, example,
+//! sidebar, quote, passthrough, open) and tables, images, breaks, comments,
+//! ordered, unordered, check and description lists with `+` continuations,
+//! and the usual inline markup, links and cross references.
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+use super::{attributes, code_block, escape, ContentFormat, Rendered};
+use crate::highlight::Highlighter;
+
+pub struct AsciiDoc;
+
+impl ContentFormat for AsciiDoc {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["adoc", "asciidoc"]
+    }
+
+    fn render(&self, source: &str, highlighter: &Highlighter) -> Result<Rendered> {
+        let lines: Vec<&str> = source.lines().collect();
+        let mut renderer = Renderer {
+            highlighter,
+            attributes: HashMap::new(),
+        };
+
+        let (title, body) = renderer.header(&lines);
+        Ok(Rendered {
+            html: renderer.blocks(&lines[body..])?,
+            title,
+        })
+    }
+}
+
+const ADMONITIONS: &[(&str, &str)] = &[
+    ("NOTE", "Note"),
+    ("TIP", "Tip"),
+    ("IMPORTANT", "Important"),
+    ("WARNING", "Warning"),
+    ("CAUTION", "Caution"),
+];
+
+/// Delimiters of blocks whose content runs to a matching closing line
+const DELIMITERS: &[&str] = &["----", "....", "====", "****", "____", "++++", "////", "--", "|==="];
+
+struct Renderer<'a> {
+    highlighter: &'a Highlighter,
+    /// Document attributes set with `:name: value`
+    attributes: HashMap<String, String>,
+}
+
+impl Renderer<'_> {
+    /// Read the document header, returning the title and the line the body
+    /// starts on. Leading comments are skipped and `:name: value` entries are
+    /// recorded; without a `= Title` there's no header.
+    fn header(&mut self, lines: &[&str]) -> (Option<String>, usize) {
+        let mut i = 0;
+        while i < lines.len() && (lines[i].trim().is_empty() || is_line_comment(lines[i])) {
+            i += 1;
+        }
+
+        let Some(title) = lines.get(i).and_then(|line| line.strip_prefix("= ")) else {
+            return (None, 0);
+        };
+        let title = self.substitute_attributes(title.trim());
+        i += 1;
+
+        while i < lines.len() && !lines[i].trim().is_empty() {
+            if let Some((name, value)) = attribute_entry(lines[i]) {
+                self.set_attribute(name, value);
+            }
+            // Anything else in the header is an author or revision line
+            i += 1;
+        }
+
+        (Some(title), i)
+    }
+
+    fn set_attribute(&mut self, name: &str, value: &str) {
+        match name.strip_suffix('!') {
+            Some(name) => {
+                self.attributes.remove(name);
+            }
+            None => {
+                self.attributes.insert(name.to_string(), value.to_string());
+            }
+        }
+    }
+
+    fn blocks(&mut self, lines: &[&str]) -> Result<String> {
+        let mut html = String::new();
+        let mut attrs = BlockAttrs::default();
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = lines[i];
+            let trimmed = line.trim_end();
+
+            if trimmed.is_empty() || trimmed == "+" {
+                i += 1;
+                continue;
+            }
+            if trimmed == "////" {
+                i = closing_delimiter(lines, i).map_or(lines.len(), |end| end + 1);
+                continue;
+            }
+            if is_line_comment(trimmed) || trimmed == "<<<" {
+                i += 1;
+                continue;
+            }
+            if let Some((name, value)) = attribute_entry(trimmed) {
+                self.set_attribute(name, value);
+                i += 1;
+                continue;
+            }
+            if let Some(id) = trimmed.strip_prefix("[[").and_then(|l| l.strip_suffix("]]")) {
+                attrs.id = Some(id.split(',').next().unwrap_or(id).trim().to_string());
+                i += 1;
+                continue;
+            }
+            if let Some(list) = trimmed.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                attrs.parse(list);
+                i += 1;
+                continue;
+            }
+            if let Some(title) = block_title(trimmed) {
+                attrs.title = Some(title.to_string());
+                i += 1;
+                continue;
+            }
+
+            let (block, next) = if trimmed == "'''" || trimmed == "---" || trimmed == "***" {
+                ("<hr>\n".to_string(), i + 1)
+            } else if let Some(level) = section_level(trimmed) {
+                let text = self.inline(trimmed[level..].trim());
+                let heading = format!(
+                    "<h{0}{1}>{2}</h{0}>\n",
+                    level,
+                    attributes(attrs.id.as_deref(), &attrs.roles),
+                    text
+                );
+                (heading, i + 1)
+            } else if let Some(macro_text) = trimmed.strip_prefix("image::") {
+                (self.image_block(macro_text, &attrs), i + 1)
+            } else if DELIMITERS.contains(&trimmed) || is_long_delimiter(trimmed) {
+                let end = closing_delimiter(lines, i).unwrap_or(lines.len());
+                let block = self.delimited(trimmed, &lines[i + 1..end], &attrs)?;
+                (block, end + 1)
+            } else if list_item(trimmed).is_some() {
+                self.list(lines, i)?
+            } else if line.starts_with(' ') || line.starts_with('\t') {
+                let mut j = i;
+                while j < lines.len() && !lines[j].trim().is_empty() {
+                    j += 1;
+                }
+                let indent = lines[i..j].iter().map(|l| l.len() - l.trim_start().len()).min().unwrap_or(0);
+                let text: Vec<&str> = lines[i..j].iter().map(|l| &l[indent.min(l.len())..]).collect();
+                let pre = format!("<pre><code>{}\n</code></pre>\n", escape(&text.join("\n")));
+                (titled(&attrs, pre), j)
+            } else {
+                let mut j = i;
+                while j < lines.len() && !lines[j].trim().is_empty() {
+                    j += 1;
+                }
+                let text: Vec<&str> = lines[i..j].iter().map(|l| l.trim()).collect();
+                (self.paragraph(&text.join("\n"), &attrs)?, j)
+            };
+
+            html.push_str(&block);
+            attrs = BlockAttrs::default();
+            i = next;
+        }
+
+        Ok(html)
+    }
+
+    fn paragraph(&mut self, text: &str, attrs: &BlockAttrs) -> Result<String> {
+        let style = attrs.style.as_deref().unwrap_or("");
+        if let Some(label) = admonition_label(style) {
+            let content = format!("<p>{}</p>\n", self.inline(text));
+            return Ok(admonition(style, label, &content, attrs));
+        }
+        for (name, label) in ADMONITIONS {
+            if let Some(rest) = text.strip_prefix(name).and_then(|t| t.strip_prefix(": ")) {
+                let content = format!("<p>{}</p>\n", self.inline(rest));
+                return Ok(admonition(name, label, &content, attrs));
+            }
+        }
+
+        let html = match style {
+            "source" | "listing" => {
+                let code = format!("{}\n", text);
+                code_block(&code, self.source_language(attrs).as_deref(), self.highlighter)?
+            }
+            "literal" => format!("<pre><code>{}\n</code></pre>\n", escape(text)),
+            "quote" | "verse" => {
+                let content = format!("<p>{}</p>\n", self.inline(text));
+                return Ok(self.quote(&content, attrs));
+            }
+            "pass" => format!("{}\n", text),
+            _ => format!(
+                "<p{}>{}</p>\n",
+                attributes(attrs.id.as_deref(), &attrs.roles),
+                self.inline(text)
+            ),
+        };
+        Ok(titled(attrs, html))
+    }
+
+    fn delimited(&mut self, delimiter: &str, content: &[&str], attrs: &BlockAttrs) -> Result<String> {
+        let style = attrs.style.as_deref().unwrap_or("");
+        let html = match &delimiter[..delimiter.len().min(4)] {
+            "----" => {
+                let code = content.iter().map(|l| format!("{}\n", l)).collect::<String>();
+                let language = match style {
+                    "source" | "" => self.source_language(attrs),
+                    _ => None,
+                };
+                code_block(&code, language.as_deref(), self.highlighter)?
+            }
+            "...." => {
+                let code = content.join("\n");
+                format!("<pre><code>{}\n</code></pre>\n", escape(&code))
+            }
+            "++++" => return Ok(content.iter().map(|l| format!("{}\n", l)).collect()),
+            "////" => return Ok(String::new()),
+            "|===" => return Ok(titled(attrs, self.table(content, attrs))),
+            "____" => {
+                let inner = self.blocks(content)?;
+                return Ok(self.quote(&inner, attrs));
+            }
+            _ => {
+                let inner = self.blocks(content)?;
+                if let Some(label) = admonition_label(style) {
+                    return Ok(admonition(style, label, &inner, attrs));
+                }
+                let class = match delimiter {
+                    "====" => "example",
+                    "****" => "sidebar",
+                    _ => "open",
+                };
+                let mut roles = vec![class.to_string()];
+                roles.extend(attrs.roles.iter().cloned());
+                let tag = if class == "sidebar" { "aside" } else { "div" };
+                let title = attrs
+                    .title
+                    .as_ref()
+                    .map(|t| format!("<p class=\"title\">{}</p>\n", self.inline(t)))
+                    .unwrap_or_default();
+                return Ok(format!(
+                    "<{0}{1}>\n{2}{3}</{0}>\n",
+                    tag,
+                    attributes(attrs.id.as_deref(), &roles),
+                    title,
+                    inner
+                ));
+            }
+        };
+        Ok(titled(attrs, html))
+    }
+
+    fn quote(&mut self, content: &str, attrs: &BlockAttrs) -> String {
+        let attribution = attrs
+            .positional
+            .iter()
+            .filter(|p| !p.is_empty())
+            .map(|p| self.inline(p))
+            .collect::<Vec<_>>();
+        let footer = if attribution.is_empty() {
+            String::new()
+        } else {
+            format!("<footer>— {}</footer>\n", attribution.join(", "))
+        };
+        titled(
+            attrs,
+            format!(
+                "<blockquote{}>\n{}{}</blockquote>\n",
+                attributes(attrs.id.as_deref(), &attrs.roles),
+                content,
+                footer
+            ),
+        )
+    }
+
+    fn source_language(&self, attrs: &BlockAttrs) -> Option<String> {
+        attrs
+            .positional
+            .first()
+            .filter(|l| !l.is_empty())
+            .cloned()
+            .or_else(|| self.attributes.get("source-language").cloned())
+    }
+
+    fn image_block(&mut self, macro_text: &str, attrs: &BlockAttrs) -> String {
+        let (target, list) = macro_parts(macro_text);
+        let img = self.image(target, list);
+        let caption = attrs
+            .title
+            .as_ref()
+            .map(|t| format!("<figcaption>{}</figcaption>\n", self.inline(t)))
+            .unwrap_or_default();
+        format!(
+            "<figure{}>\n{}\n{}</figure>\n",
+            attributes(attrs.id.as_deref(), &attrs.roles),
+            img,
+            caption
+        )
+    }
+
+    fn image(&self, target: &str, list: &str) -> String {
+        let parts: Vec<&str> = list.split(',').map(str::trim).collect();
+        let alt = parts
+            .first()
+            .filter(|a| !a.is_empty())
+            .map(|a| a.trim_matches('"').to_string())
+            .unwrap_or_else(|| default_alt(target));
+        let mut html = format!(
+            "<img src=\"{}\" alt=\"{}\"",
+            escape(&self.substitute_attributes(target)),
+            escape(&alt)
+        );
+        for (name, value) in ["width", "height"].iter().zip(parts.iter().skip(1)) {
+            if !value.is_empty() {
+                html.push_str(&format!(" {}=\"{}\"", name, escape(value)));
+            }
+        }
+        html.push('>');
+        html
+    }
+
+    fn table(&mut self, content: &[&str], attrs: &BlockAttrs) -> String {
+        let mut cells: Vec<String> = Vec::new();
+        let mut first_line_cells = None;
+        let mut blank_after_first = false;
+
+        for (n, line) in content.iter().enumerate() {
+            if line.trim().is_empty() {
+                if first_line_cells.is_some() && n == 1 {
+                    blank_after_first = true;
+                }
+                continue;
+            }
+            let parts = split_cells(line);
+            let mut parts = parts.into_iter();
+            // Text before the first `|` continues the previous cell
+            if let Some(lead) = parts.next() {
+                if !lead.trim().is_empty() {
+                    if let Some(last) = cells.last_mut() {
+                        last.push('\n');
+                        last.push_str(lead.trim());
+                    }
+                }
+            }
+            let before = cells.len();
+            cells.extend(parts.map(|c| c.trim().to_string()));
+            if first_line_cells.is_none() {
+                first_line_cells = Some(cells.len() - before);
+            }
+        }
+
+        let columns = attrs
+            .named
+            .get("cols")
+            .and_then(|cols| column_count(cols))
+            .or(first_line_cells)
+            .unwrap_or(1)
+            .max(1);
+        let header = attrs.options.iter().any(|o| o == "header")
+            || (!attrs.options.iter().any(|o| o == "noheader")
+                && blank_after_first
+                && first_line_cells == Some(columns));
+
+        let mut html = format!("<table{}>\n", attributes(attrs.id.as_deref(), &attrs.roles));
+        for (r, row) in cells.chunks(columns).enumerate() {
+            let tag = if header && r == 0 { "th" } else { "td" };
+            html.push_str("<tr>\n");
+            for cell in row {
+                html.push_str(&format!("<{0}>{1}</{0}>\n", tag, self.inline(cell)));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+        html
+    }
+
+    /// Render the list starting at `lines[start]` and return where it ends.
+    /// Items run until the next item with the same marker; lines with other
+    /// markers are nested lists, and `+` attaches the following block.
+    fn list(&mut self, lines: &[&str], start: usize) -> Result<(String, usize)> {
+        let first = list_item(lines[start].trim_end()).expect("list starts with an item");
+        let mut items = Vec::new();
+        let mut i = start;
+
+        while i < lines.len() {
+            let Some(item) = list_item(lines[i].trim_end()).filter(|it| it.marker == first.marker) else {
+                break;
+            };
+            let mut text = vec![item.text];
+            i += 1;
+            while i < lines.len() {
+                let line = lines[i].trim();
+                if line.is_empty() || line == "+" || list_item(line).is_some() {
+                    break;
+                }
+                text.push(line);
+                i += 1;
+            }
+
+            let body_start = i;
+            while i < lines.len() {
+                let line = lines[i].trim();
+                if line == "+" {
+                    i = attached_block_end(lines, i + 1);
+                } else if let Some(nested) = list_item(line) {
+                    if nested.marker == first.marker {
+                        break;
+                    }
+                    i += 1;
+                } else if line.is_empty() {
+                    let next = (i..lines.len()).find(|&j| !lines[j].trim().is_empty());
+                    match next {
+                        Some(j) if list_item(lines[j].trim()).is_some() => i = j,
+                        _ => break,
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            items.push((item, text, &lines[body_start..i]));
+
+            // Blank lines between items don't end the list
+            let next = (i..lines.len()).find(|&j| !lines[j].trim().is_empty());
+            match next {
+                Some(j) if list_item(lines[j].trim()).is_some_and(|it| it.marker == first.marker) => i = j,
+                _ => break,
+            }
+        }
+
+        let checklist = items.iter().any(|(item, _, _)| item.checked.is_some());
+        let (open, close) = match first.kind {
+            ListKind::Unordered if checklist => ("<ul class=\"checklist\">", "</ul>"),
+            ListKind::Unordered => ("<ul>", "</ul>"),
+            ListKind::Ordered => ("<ol>", "</ol>"),
+            ListKind::Description => ("<dl>", "</dl>"),
+        };
+
+        let mut html = format!("{}\n", open);
+        for (item, mut text, body) in items {
+            text.retain(|t| !t.is_empty());
+            let text = self.inline(&text.join("\n"));
+            let body = self.blocks(body)?;
+            match first.kind {
+                ListKind::Description => {
+                    let term = self.inline(item.term.unwrap_or_default());
+                    html.push_str(&format!("<dt>{}</dt>\n<dd>\n", term));
+                    if !text.is_empty() {
+                        html.push_str(&format!("<p>{}</p>\n", text));
+                    }
+                    html.push_str(&format!("{}</dd>\n", body));
+                }
+                _ => {
+                    let checkbox = match item.checked {
+                        Some(true) => "<input type=\"checkbox\" disabled checked> ",
+                        Some(false) => "<input type=\"checkbox\" disabled> ",
+                        None => "",
+                    };
+                    html.push_str(&format!("<li>\n<p>{}{}</p>\n{}</li>\n", checkbox, text, body));
+                }
+            }
+        }
+        html.push_str(close);
+        html.push('\n');
+
+        Ok((html, i))
+    }
+
+    fn inline(&self, text: &str) -> String {
+        let text = self.substitute_attributes(text);
+        self.quotes(&text)
+    }
+
+    /// Replace `{name}` with the attribute's value; unknown names are kept
+    fn substitute_attributes(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(open) = rest.find('{') {
+            let escaped = rest[..open].ends_with('\\');
+            result.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            let close = after.find('}');
+            let name = close.map(|c| &after[..c]);
+            let value = name.and_then(|n| {
+                let valid = !n.is_empty() && n.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+                if valid {
+                    self.attributes.get(n).map(String::as_str).or_else(|| builtin_attribute(n))
+                } else {
+                    None
+                }
+            });
+
+            match (value, close) {
+                (Some(value), Some(close)) if !escaped => {
+                    result.push_str(value);
+                    rest = &after[close + 1..];
+                }
+                _ => {
+                    if escaped {
+                        result.pop();
+                    }
+                    result.push('{');
+                    rest = after;
+                }
+            }
+        }
+
+        result.push_str(rest);
+        result
+    }
+
+    /// Inline markup, links and macros
+    fn quotes(&self, text: &str) -> String {
+        let mut html = String::new();
+        let mut previous: Option<char> = None;
+        let mut i = 0;
+
+        while let Some(c) = text[i..].chars().next() {
+            let rest = &text[i..];
+            let at_boundary = previous.is_none_or(|p| !p.is_alphanumeric());
+
+            // Each arm produces the HTML and the number of bytes consumed
+            let element: Option<(String, usize)> = match c {
+                '\\' => rest[1..]
+                    .chars()
+                    .next()
+                    .filter(|n| "*_`#^~+[<{\\".contains(*n))
+                    .map(|n| (escape(&n.to_string()), 1 + n.len_utf8())),
+                ' ' if rest.starts_with(" +\n") => Some(("<br>\n".to_string(), 3)),
+                ' ' if rest == " +" => Some(("<br>".to_string(), 2)),
+                '`' => self.code(rest, at_boundary),
+                '+' => passthrough(rest, at_boundary),
+                '*' | '_' | '#' => {
+                    let tag = match c {
+                        '*' => "strong",
+                        '_' => "em",
+                        _ => "mark",
+                    };
+                    quoted(rest, c, at_boundary).map(|(inner, len)| {
+                        (format!("<{0}>{1}</{0}>", tag, self.quotes(inner)), len)
+                    })
+                }
+                '^' | '~' => {
+                    let tag = if c == '^' { "sup" } else { "sub" };
+                    let body = &rest[1..];
+                    body.find(c)
+                        .filter(|&end| end > 0 && !body[..end].contains(char::is_whitespace))
+                        .map(|end| (format!("<{0}>{1}</{0}>", tag, self.quotes(&body[..end])), end + 2))
+                }
+                '<' if rest.starts_with("<<") => self.cross_reference(rest),
+                '&' => {
+                    let entity = rest[1..]
+                        .find(';')
+                        .filter(|&end| end > 0 && rest[1..1 + end].chars().all(|c| c.is_alphanumeric() || c == '#'));
+                    Some(match entity {
+                        Some(end) => (rest[..end + 2].to_string(), end + 2),
+                        None => ("&amp;".to_string(), 1),
+                    })
+                }
+                _ if at_boundary => self.macro_at(rest),
+                _ => None,
+            };
+
+            match element {
+                Some((element, len)) => {
+                    html.push_str(&element);
+                    previous = rest[..len].chars().last();
+                    i += len;
+                }
+                None => {
+                    html.push_str(&escape(&c.to_string()));
+                    previous = Some(c);
+                    i += c.len_utf8();
+                }
+            }
+        }
+
+        html
+    }
+
+    fn code(&self, text: &str, at_boundary: bool) -> Option<(String, usize)> {
+        let (inner, len) = quoted(text, '`', at_boundary)?;
+        // `+text+` inside backticks is explicitly literal
+        let inner = inner
+            .strip_prefix('+')
+            .and_then(|i| i.strip_suffix('+'))
+            .unwrap_or(inner);
+        Some((format!("<code>{}</code>", escape(inner)), len))
+    }
+
+    /// `<<id>>`, `<<id,text>>` and `<<doc.adoc#id,text>>`
+    fn cross_reference(&self, text: &str) -> Option<(String, usize)> {
+        let close = text.find(">>")?;
+        let inner = &text[2..close];
+        if inner.is_empty() || inner.contains('\n') {
+            return None;
+        }
+        let (target, label) = match inner.split_once(',') {
+            Some((target, label)) => (target.trim(), label.trim()),
+            None => (inner.trim(), inner.trim()),
+        };
+        let href = if target.contains('#') || target.ends_with(".adoc") {
+            document_href(target)
+        } else {
+            format!("#{}", target)
+        };
+        Some((
+            format!("<a href=\"{}\">{}</a>", escape(&href), self.quotes(label)),
+            close + 2,
+        ))
+    }
+
+    /// URLs and the `link:`, `mailto:`, `xref:` and `image:` macros
+    fn macro_at(&self, text: &str) -> Option<(String, usize)> {
+        if let Some(rest) = text.strip_prefix("image:").filter(|r| !r.starts_with(':')) {
+            let (target, list, len) = macro_call(rest)?;
+            return Some((self.image(target, list), "image:".len() + len));
+        }
+        for (prefix, keep_prefix) in [("link:", false), ("xref:", false), ("mailto:", true)] {
+            if let Some(rest) = text.strip_prefix(prefix) {
+                let (target, label, len) = macro_call(rest)?;
+                let href = match prefix {
+                    "xref:" => document_href(target),
+                    _ if keep_prefix => format!("{}{}", prefix, target),
+                    _ => target.to_string(),
+                };
+                let fallback = if keep_prefix { target } else { &href };
+                return Some((self.link(&href, label, fallback), prefix.len() + len));
+            }
+        }
+
+        if !(text.starts_with("https://") || text.starts_with("http://")) {
+            return None;
+        }
+        let end = text
+            .find(|c: char| c.is_whitespace() || c == '[' || c == '<' || c == '"')
+            .unwrap_or(text.len());
+        if text[end..].starts_with('[') {
+            let (target, label, len) = macro_call(text)?;
+            return Some((self.link(target, label, target), len));
+        }
+        // Trailing punctuation belongs to the sentence, not the URL
+        let url = text[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')']);
+        Some((self.link(url, "", url), url.len()))
+    }
+
+    /// A link; a trailing `^` on the label opens it in a new window
+    fn link(&self, href: &str, label: &str, fallback: &str) -> String {
+        let (label, blank) = match label.strip_suffix('^') {
+            Some(label) => (label, true),
+            None => (label, false),
+        };
+        let label = match label.trim().trim_matches('"') {
+            "" => escape(fallback),
+            label => self.quotes(label),
+        };
+        let target = if blank { " target=\"_blank\" rel=\"noopener\"" } else { "" };
+        format!("<a href=\"{}\"{}>{}</a>", escape(href), target, label)
+    }
+}
+
+/// Attributes collected from the lines above a block
+#[derive(Debug, Default)]
+struct BlockAttrs {
+    id: Option<String>,
+    roles: Vec<String>,
+    style: Option<String>,
+    /// Positional attributes after the style, such as a source language
+    positional: Vec<String>,
+    options: Vec<String>,
+    named: HashMap<String, String>,
+    title: Option<String>,
+}
+
+impl BlockAttrs {
+    /// Merge an attribute list like `source,rust`, `#intro.lead` or
+    /// `cols="1,2",options="header"`
+    fn parse(&mut self, list: &str) {
+        for (n, item) in split_attribute_list(list).into_iter().enumerate() {
+            if let Some((name, value)) = item.split_once('=') {
+                let value = value.trim().trim_matches('"').to_string();
+                match name.trim() {
+                    "id" => self.id = Some(value),
+                    "role" => self.roles.extend(value.split_whitespace().map(String::from)),
+                    "options" | "opts" => self.options.extend(value.split(',').map(|o| o.trim().to_string())),
+                    name => {
+                        self.named.insert(name.to_string(), value);
+                    }
+                }
+            } else if n == 0 {
+                self.parse_style(item.trim());
+            } else {
+                self.positional.push(item.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+
+    /// The first positional attribute: a style with `#id`, `.role` and
+    /// `%option` shorthands
+    fn parse_style(&mut self, text: &str) {
+        let split = |s: &str| s.find(['#', '.', '%']).unwrap_or(s.len());
+        let end = split(text);
+        if end > 0 {
+            self.style = Some(text[..end].to_string());
+        }
+
+        let mut rest = &text[end..];
+        while let Some(marker) = rest.chars().next() {
+            let value_end = 1 + split(&rest[1..]);
+            let value = rest[1..value_end].to_string();
+            match marker {
+                '#' => self.id = Some(value),
+                '.' => self.roles.push(value),
+                _ => self.options.push(value),
+            }
+            rest = &rest[value_end..];
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ListKind {
+    Unordered,
+    Ordered,
+    Description,
+}
+
+#[derive(Debug)]
+struct ListItem<'a> {
+    kind: ListKind,
+    /// Marker normalised for comparison: `*`, `**`, `-`, `.`, `::`, ...
+    marker: String,
+    term: Option<&'a str>,
+    text: &'a str,
+    checked: Option<bool>,
+}
+
+fn list_item(line: &str) -> Option<ListItem<'_>> {
+    let line = line.trim_start();
+
+    let stars = line.len() - line.trim_start_matches('*').len();
+    let dots = line.len() - line.trim_start_matches('.').len();
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let (kind, marker, rest) = if (1..=5).contains(&stars) {
+        (ListKind::Unordered, &line[..stars], &line[stars..])
+    } else if line.starts_with('-') && !line.starts_with("--") {
+        (ListKind::Unordered, "-", &line[1..])
+    } else if (1..=5).contains(&dots) {
+        (ListKind::Ordered, &line[..dots], &line[dots..])
+    } else if digits > 0 && line[digits..].starts_with('.') {
+        (ListKind::Ordered, ".", &line[digits + 1..])
+    } else {
+        return description_item(line);
+    };
+
+    let text = rest.strip_prefix([' ', '\t'])?.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    let mut item = ListItem {
+        kind,
+        marker: marker.to_string(),
+        term: None,
+        text,
+        checked: None,
+    };
+    if kind == ListKind::Unordered {
+        for (box_text, checked) in [("[ ] ", false), ("[x] ", true), ("[*] ", true)] {
+            if let Some(rest) = text.strip_prefix(box_text) {
+                item.text = rest;
+                item.checked = Some(checked);
+            }
+        }
+    }
+    Some(item)
+}
+
+/// `term:: description`, with `:::`, `::::` or `;;` for nested lists
+fn description_item(line: &str) -> Option<ListItem<'_>> {
+    for marker in ["::::", ":::", "::", ";;"] {
+        let mut search = 0;
+        while let Some(found) = line[search..].find(marker) {
+            let at = search + found;
+            let after = &line[at + marker.len()..];
+            let before = &line[..at];
+            if !before.trim().is_empty()
+                && !before.ends_with(':')
+                && !after.starts_with(':')
+                && (after.is_empty() || after.starts_with([' ', '\t']))
+            {
+                return Some(ListItem {
+                    kind: ListKind::Description,
+                    marker: marker.to_string(),
+                    term: Some(before.trim()),
+                    text: after.trim(),
+                    checked: None,
+                });
+            }
+            search = at + marker.len();
+        }
+    }
+    None
+}
+
+/// Heading level of a `== Section` title, which is the number of `=`; a
+/// level-0 `= Title` in the body renders as `<h1>`
+fn section_level(line: &str) -> Option<usize> {
+    let equals = line.len() - line.trim_start_matches('=').len();
+    ((1..=6).contains(&equals) && line[equals..].starts_with(' ') && !line[equals..].trim().is_empty())
+        .then_some(equals)
+}
+
+/// `.Title` above a block
+fn block_title(line: &str) -> Option<&str> {
+    let title = line.strip_prefix('.')?;
+    title
+        .chars()
+        .next()
+        .filter(|c| !c.is_whitespace() && *c != '.')
+        .map(|_| title)
+}
+
+/// `:name: value`, `:name:` or `:name!:`
+fn attribute_entry(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix(':')?;
+    let close = rest.find(':')?;
+    let name = &rest[..close];
+    let value = &rest[close + 1..];
+    let valid = !name.is_empty()
+        && name
+            .trim_end_matches('!')
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    (valid && (value.is_empty() || value.starts_with(' '))).then(|| (name, value.trim()))
+}
+
+fn is_line_comment(line: &str) -> bool {
+    line.starts_with("//") && !line.starts_with("////")
+}
+
+/// A delimiter made longer than the minimum, like `------`
+fn is_long_delimiter(line: &str) -> bool {
+    line.len() > 4
+        && ["-", ".", "=", "*", "_", "+", "/"]
+            .iter()
+            .any(|c| line.chars().all(|l| l.to_string() == *c))
+}
+
+/// Index of the line closing the delimited block opened at `lines[start]`
+fn closing_delimiter(lines: &[&str], start: usize) -> Option<usize> {
+    let delimiter = lines[start].trim_end();
+    (start + 1..lines.len()).find(|&j| lines[j].trim_end() == delimiter)
+}
+
+/// End of the block attached to a list item by a `+` line
+fn attached_block_end(lines: &[&str], start: usize) -> usize {
+    let mut i = start;
+    while i < lines.len() {
+        let line = lines[i].trim_end();
+        if line.starts_with('[') && line.ends_with(']') || block_title(line).is_some() {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    if i < lines.len() {
+        let line = lines[i].trim_end();
+        if DELIMITERS.contains(&line) || is_long_delimiter(line) {
+            return closing_delimiter(lines, i).map_or(lines.len(), |end| end + 1);
+        }
+    }
+    while i < lines.len() && !lines[i].trim().is_empty() {
+        i += 1;
+    }
+    i
+}
+
+fn admonition_label(style: &str) -> Option<&'static str> {
+    ADMONITIONS.iter().find(|(name, _)| *name == style).map(|(_, label)| *label)
+}
+
+fn admonition(style: &str, label: &str, content: &str, attrs: &BlockAttrs) -> String {
+    let mut roles = vec!["admonition".to_string(), style.to_ascii_lowercase()];
+    roles.extend(attrs.roles.iter().cloned());
+    format!(
+        "<div{}>\n<p class=\"admonition-title\">{}</p>\n{}</div>\n",
+        attributes(attrs.id.as_deref(), &roles),
+        attrs.title.as_deref().map(escape).unwrap_or_else(|| label.to_string()),
+        content
+    )
+}
+
+/// Put a block's `.Title` above it
+fn titled(attrs: &BlockAttrs, html: String) -> String {
+    match &attrs.title {
+        Some(title) => format!("<p class=\"title\">{}</p>\n{}", escape(title), html),
+        None => html,
+    }
+}
+
+/// Span of constrained (`*x*`) or unconstrained (`**x**`) markup opening
+/// `text`: its content and total length. Constrained markup needs word
+/// boundaries on the outside and no space just inside.
+fn quoted(text: &str, mark: char, at_boundary: bool) -> Option<(&str, usize)> {
+    let double: String = [mark, mark].iter().collect();
+    if let Some(body) = text.strip_prefix(double.as_str()) {
+        if let Some(end) = body.find(double.as_str()).filter(|&end| end > 0) {
+            return Some((&body[..end], end + 4));
+        }
+    }
+
+    if !at_boundary {
+        return None;
+    }
+    let body = &text[1..];
+    if body.starts_with(char::is_whitespace) || body.starts_with(mark) {
+        return None;
+    }
+    let mut search = 0;
+    while let Some(found) = body[search..].find(mark) {
+        let end = search + found;
+        let inner = &body[..end];
+        let after = body[end + 1..].chars().next();
+        if !inner.is_empty() && !inner.ends_with(char::is_whitespace) && after.is_none_or(|a| !a.is_alphanumeric()) {
+            return Some((inner, end + 2));
+        }
+        search = end + 1;
+    }
+    None
+}
+
+/// `+++raw+++`, `++text++` and `+text+` passthroughs
+fn passthrough(text: &str, at_boundary: bool) -> Option<(String, usize)> {
+    if let Some(body) = text.strip_prefix("+++") {
+        let end = body.find("+++")?;
+        return Some((body[..end].to_string(), end + 6));
+    }
+    let (inner, len) = quoted(text, '+', at_boundary)?;
+    Some((escape(inner), len))
+}
+
+/// `target[attributes]` of a macro, and its length
+fn macro_call(text: &str) -> Option<(&str, &str, usize)> {
+    let open = text.find('[')?;
+    let target = &text[..open];
+    if target.is_empty() || target.contains(char::is_whitespace) {
+        return None;
+    }
+    let close = open + text[open..].find(']')?;
+    Some((target, &text[open + 1..close], close + 1))
+}
+
+/// Target and attribute list of a block macro such as `image::a.png[Alt]`
+fn macro_parts(text: &str) -> (&str, &str) {
+    match text.find('[') {
+        Some(open) => (&text[..open], text[open + 1..].trim_end_matches(']')),
+        None => (text, ""),
+    }
+}
+
+/// Link to another document, left pointing at its source so it resolves to
+/// wherever that page ends up: `other#id` becomes `other.adoc#id`
+fn document_href(target: &str) -> String {
+    let (document, fragment) = match target.split_once('#') {
+        Some((document, fragment)) => (document, Some(fragment)),
+        None => (target, None),
+    };
+    let document = match document.rsplit('/').next().unwrap_or_default() {
+        name if !name.is_empty() && !name.contains('.') => format!("{}.adoc", document),
+        _ => document.to_string(),
+    };
+    match fragment {
+        Some(fragment) => format!("{}#{}", document, fragment),
+        None => document,
+    }
+}
+
+fn default_alt(target: &str) -> String {
+    let name = target.rsplit('/').next().unwrap_or(target);
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    stem.replace(['-', '_'], " ")
+}
+
+/// Split an attribute list on commas outside double quotes
+fn split_attribute_list(list: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in list.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                items.push(&list[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&list[start..]);
+    items
+}
+
+/// Segments of a table line split on unescaped `|`; the first is whatever
+/// came before the first separator
+fn split_cells(line: &str) -> Vec<String> {
+    let mut cells = vec![String::new()];
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cells.last_mut().unwrap().push('|');
+                chars.next();
+            }
+            '|' => cells.push(String::new()),
+            _ => cells.last_mut().unwrap().push(c),
+        }
+    }
+    cells
+}
+
+/// Columns given by `cols="1,2,3"` or `cols="3*"`
+fn column_count(cols: &str) -> Option<usize> {
+    match cols.split_once('*') {
+        Some((count, _)) => count.trim().parse().ok(),
+        None => Some(cols.split(',').count()),
+    }
+}
+
+fn builtin_attribute(name: &str) -> Option<&'static str> {
+    match name {
+        "empty" => Some(""),
+        "sp" => Some(" "),
+        "nbsp" => Some("&#160;"),
+        "amp" => Some("&amp;"),
+        "lt" => Some("&lt;"),
+        "gt" => Some("&gt;"),
+        "startsb" => Some("["),
+        "endsb" => Some("]"),
+        "vbar" => Some("|"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HighlightConfig;
+
+    fn render(source: &str) -> Rendered {
+        let highlighter = Highlighter::new(&HighlightConfig::default()).unwrap();
+        AsciiDoc.render(source, &highlighter).unwrap()
+    }
+
+    #[test]
+    fn test_asciidoc_header_and_sections() {
+        let rendered = render(
+            "// SPDX-License-Identifier: PMPL-1.0-or-later\n= WokeLang SSG\n:toc:\n:project: WokeLang\n\n\
+             == Overview\n\n{project} is written *in {project} itself*.\n\n[[stack]]\n=== Technology Stack\n",
+        );
+
+        assert_eq!(rendered.title.as_deref(), Some("WokeLang SSG"));
+        assert_eq!(
+            rendered.html,
+            "<h2>Overview</h2>\n<p>WokeLang is written <strong>in WokeLang itself</strong>.</p>\n\
+             <h3 id=\"stack\">Technology Stack</h3>\n"
+        );
+    }
+
+    #[test]
+    fn test_asciidoc_blocks() {
+        let rendered = render(
+            "[source,woke]\n----\nto main() {\n}\n----\n\n----\nplain <box>\n----\n\n\
+             NOTE: Consent comes first.\n\n[WARNING]\n====\nNo tracking.\n====\n\n\
+             .Tools\n[cols=\"1,2\",options=\"header\"]\n|===\n|Tool |Use\n|Rust |CLI\n|===\n\n\
+             ____\nBe kind.\n____\n\n'''\n\nimage::logo.png[Logo,64]\n\n  indented literal\n\n\
+             ////\nhidden\n////\n",
+        );
+        let html = rendered.html;
+
+        assert!(rendered.title.is_none());
+        assert!(html.starts_with("<pre style="));
+        assert!(html.contains("<pre><code>plain &lt;box&gt;\n</code></pre>\n"));
+        assert!(html.contains(
+            "<div class=\"admonition note\">\n<p class=\"admonition-title\">Note</p>\n<p>Consent comes first.</p>\n</div>\n"
+        ));
+        assert!(html.contains(
+            "<div class=\"admonition warning\">\n<p class=\"admonition-title\">Warning</p>\n<p>No tracking.</p>\n</div>\n"
+        ));
+        assert!(html.contains(
+            "<p class=\"title\">Tools</p>\n<table>\n<tr>\n<th>Tool</th>\n<th>Use</th>\n</tr>\n\
+             <tr>\n<td>Rust</td>\n<td>CLI</td>\n</tr>\n</table>\n"
+        ));
+        assert!(html.contains("<blockquote>\n<p>Be kind.</p>\n</blockquote>\n<hr>\n"));
+        assert!(html.contains("<figure>\n<img src=\"logo.png\" alt=\"Logo\" width=\"64\">\n</figure>\n"));
+        assert!(html.ends_with("<pre><code>indented literal\n</code></pre>\n"));
+        assert!(!html.contains("hidden"));
+    }
+
+    #[test]
+    fn test_asciidoc_lists() {
+        let html = render(
+            "* one\n** nested\n* two\n+\n----\ncode\n----\n\n//\n. first\n. second\n\n//\n\
+             * [x] done\n* [ ] todo\n\n//\nCLI:: command line\nSSG::\n  static sites\n",
+        )
+        .html;
+
+        assert_eq!(
+            html,
+            "<ul>\n<li>\n<p>one</p>\n<ul>\n<li>\n<p>nested</p>\n</li>\n</ul>\n</li>\n\
+             <li>\n<p>two</p>\n<pre><code>code\n</code></pre>\n</li>\n</ul>\n\
+             <ol>\n<li>\n<p>first</p>\n</li>\n<li>\n<p>second</p>\n</li>\n</ol>\n\
+             <ul class=\"checklist\">\n<li>\n<p><input type=\"checkbox\" disabled checked> done</p>\n</li>\n\
+             <li>\n<p><input type=\"checkbox\" disabled> todo</p>\n</li>\n</ul>\n\
+             <dl>\n<dt>CLI</dt>\n<dd>\n<p>command line</p>\n</dd>\n<dt>SSG</dt>\n<dd>\n<p>static sites</p>\n</dd>\n</dl>\n"
+        );
+    }
+
+    #[test]
+    fn test_asciidoc_inlines() {
+        let html = render(
+            "`a < b` **bold**er _em_ #marked# x^2^ H~2~O +*literal*+ snake_case_name\n\
+             https://wokelang.org[WokeLang^] and https://example.org. link:/docs/[Docs] \
+             xref:guide.adoc#setup[Setup] <<stack,the stack>> image:icon.svg[] R&amp;D & more +\nnext\n",
+        )
+        .html;
+
+        assert_eq!(
+            html,
+            "<p><code>a &lt; b</code> <strong>bold</strong>er <em>em</em> <mark>marked</mark> x<sup>2</sup> \
+             H<sub>2</sub>O *literal* snake_case_name\n\
+             <a href=\"https://wokelang.org\" target=\"_blank\" rel=\"noopener\">WokeLang</a> and \
+             <a href=\"https://example.org\">https://example.org</a>. <a href=\"/docs/\">Docs</a> \
+             <a href=\"guide.adoc#setup\">Setup</a> <a href=\"#stack\">the stack</a> \
+             <img src=\"icon.svg\" alt=\"icon\"> R&amp;D &amp; more<br>\nnext</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_asciidoc_attributes() {
+        let html = render(
+            ":name: WokeLang\n:empty:\n\n[#intro.lead]\n{name} {missing} {empty}done\n\n\
+             :name!:\n[[later]]\n== After {name}\n",
+        )
+        .html;
+
+        assert_eq!(
+            html,
+            "<p id=\"intro\" class=\"lead\">WokeLang {missing} done</p>\n<h2 id=\"later\">After {name}</h2>\n"
+        );
+    }
+
+    #[test]
+    fn test_asciidoc_nested_blocks() {
+        let html = render(
+            "[TIP]\n====\n* a\n* b\n====\n\nCAUTION: one\ncontinues\n\n\
+             [%header]\n|===\n|H1 |H2\n|x |y\n|===\n\n....\nliteral <b>\n....\n",
+        )
+        .html;
+
+        assert_eq!(
+            html,
+            "<div class=\"admonition tip\">\n<p class=\"admonition-title\">Tip</p>\n\
+             <ul>\n<li>\n<p>a</p>\n</li>\n<li>\n<p>b</p>\n</li>\n</ul>\n</div>\n\
+             <div class=\"admonition caution\">\n<p class=\"admonition-title\">Caution</p>\n<p>one\ncontinues</p>\n</div>\n\
+             <table>\n<tr>\n<th>H1</th>\n<th>H2</th>\n</tr>\n<tr>\n<td>x</td>\n<td>y</td>\n</tr>\n</table>\n\
+             <pre><code>literal &lt;b&gt;\n</code></pre>\n"
+        );
+    }
+}
@@ -0,0 +1,879 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Djot
+//!
+//! Covers the syntax our content uses: headings, paragraphs, bullet,
+//! ordered and task lists, block quotes, code and raw blocks, divs, tables,
+//! thematic breaks and block attributes; emphasis, strong, code, links and
+//! images (inline and by reference), autolinks, spans with attributes,
+//! highlight, insert, delete, super- and subscript and hard line breaks.
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+use super::{attributes, code_block, escape, ContentFormat, Rendered};
+use crate::highlight::Highlighter;
+
+pub struct Djot;
+
+impl ContentFormat for Djot {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["dj", "djot"]
+    }
+
+    fn render(&self, source: &str, highlighter: &Highlighter) -> Result<Rendered> {
+        let lines: Vec<&str> = source.lines().collect();
+        let renderer = Renderer {
+            highlighter,
+            references: reference_definitions(&lines),
+        };
+
+        Ok(Rendered {
+            html: renderer.blocks(&lines, false)?,
+            title: None,
+        })
+    }
+}
+
+struct Renderer<'a> {
+    highlighter: &'a Highlighter,
+    /// Link targets from `[label]: url` lines
+    references: HashMap<String, String>,
+}
+
+impl Renderer<'_> {
+    /// Render a run of block-level lines. In a tight list item paragraphs
+    /// aren't wrapped in `<p>`.
+    fn blocks<S: AsRef<str>>(&self, lines: &[S], tight: bool) -> Result<String> {
+        let lines: Vec<&str> = lines.iter().map(AsRef::as_ref).collect();
+        let mut html = String::new();
+        let mut attrs = Attrs::default();
+        let mut i = 0;
+
+        while i < lines.len() {
+            let trimmed = lines[i].trim();
+            if trimmed.is_empty() || reference_definition(trimmed).is_some() {
+                i += 1;
+                continue;
+            }
+            if let Some(block_attrs) = Attrs::parse(trimmed) {
+                attrs.merge(block_attrs);
+                i += 1;
+                continue;
+            }
+
+            let start = lines[i].trim_start();
+            let (block, next) = if let Some(level) = heading_level(start) {
+                let mut text = vec![start[level..].trim()];
+                let mut j = i + 1;
+                while j < lines.len() && !lines[j].trim().is_empty() {
+                    let line = lines[j].trim();
+                    text.push(line.strip_prefix(&"#".repeat(level)).map_or(line, str::trim));
+                    j += 1;
+                }
+                let heading = format!("<h{0}{1}>{2}</h{0}>\n", level, attrs.html(), self.inline(&text.join("\n")));
+                (heading, j)
+            } else if let Some((fence, info)) = code_fence(start) {
+                let mut j = i + 1;
+                let mut code = String::new();
+                while j < lines.len() && !closes_fence(lines[j].trim(), fence) {
+                    code.push_str(lines[j]);
+                    code.push('\n');
+                    j += 1;
+                }
+                let block = match info.strip_prefix('=') {
+                    // Raw blocks only pass through when they're for HTML
+                    Some("html") => code,
+                    Some(_) => String::new(),
+                    None => code_block(&code, Some(info), self.highlighter)?,
+                };
+                (block, j + 1)
+            } else if is_thematic_break(start) {
+                (format!("<hr{}>\n", attrs.html()), i + 1)
+            } else if let Some((colons, class)) = div_fence(start) {
+                let end = (i + 1..lines.len())
+                    .find(|&j| div_fence(lines[j].trim_start()).is_some_and(|(n, c)| n >= colons && c.is_empty()))
+                    .unwrap_or(lines.len());
+                if !class.is_empty() {
+                    attrs.classes.insert(0, class.to_string());
+                }
+                let inner = self.blocks(&lines[i + 1..end], false)?;
+                (format!("<div{}>\n{}</div>\n", attrs.html(), inner), end + 1)
+            } else if is_blockquote(start) {
+                let mut j = i;
+                let mut quoted = Vec::new();
+                while j < lines.len() && is_blockquote(lines[j].trim_start()) {
+                    let line = &lines[j].trim_start()[1..];
+                    quoted.push(line.strip_prefix(' ').unwrap_or(line));
+                    j += 1;
+                }
+                let inner = self.blocks(&quoted, false)?;
+                (format!("<blockquote{}>\n{}</blockquote>\n", attrs.html(), inner), j)
+            } else if is_table_row(start) {
+                let mut j = i;
+                while j < lines.len() && is_table_row(lines[j].trim_start()) {
+                    j += 1;
+                }
+                (self.table(&lines[i..j], &attrs), j)
+            } else if let Some(marker) = list_marker(start) {
+                self.list(&lines, i, marker)?
+            } else {
+                let mut j = i;
+                while j < lines.len() && !lines[j].trim().is_empty() {
+                    j += 1;
+                }
+                let text: Vec<&str> = lines[i..j].iter().map(|l| l.trim_start()).collect();
+                let text = self.inline(&text.join("\n"));
+                let paragraph = if tight {
+                    format!("{}\n", text)
+                } else {
+                    format!("<p{}>{}</p>\n", attrs.html(), text)
+                };
+                (paragraph, j)
+            };
+
+            html.push_str(&block);
+            attrs = Attrs::default();
+            i = next;
+        }
+
+        Ok(html)
+    }
+
+    /// Render the list starting at `lines[start]` and return where it ends
+    fn list(&self, lines: &[&str], start: usize, first: Marker) -> Result<(String, usize)> {
+        let indent = leading_spaces(lines[start]);
+        let mut items: Vec<(Marker, Vec<String>)> = Vec::new();
+        let mut loose = false;
+        let mut i = start;
+
+        while i < lines.len() && leading_spaces(lines[i]) == indent {
+            let Some(marker) = list_marker(lines[i].trim_start()) else {
+                break;
+            };
+            if !marker.kind.continues(first.kind) {
+                break;
+            }
+
+            let content_indent = indent + marker.width;
+            let mut item = vec![lines[i].trim_start()[marker.width..].to_string()];
+            let mut blank_lines = 0;
+            i += 1;
+            while i < lines.len() {
+                let line = lines[i];
+                let lead = leading_spaces(line);
+                if line.trim().is_empty() {
+                    item.push(String::new());
+                    blank_lines += 1;
+                } else if lead > indent {
+                    // A sublist may follow the item's text directly
+                    if list_marker(line.trim_start()).is_some() && item.last().is_some_and(|l| !l.is_empty()) {
+                        item.push(String::new());
+                    }
+                    item.push(line[lead.min(content_indent)..].to_string());
+                } else if item.last().is_some_and(|l| !l.is_empty()) && list_marker(line.trim_start()).is_none() {
+                    // Lazy continuation of the item's paragraph
+                    item.push(line.trim_start().to_string());
+                } else {
+                    break;
+                }
+                i += 1;
+            }
+
+            let mut trailing_blank = false;
+            while item.last().is_some_and(String::is_empty) {
+                item.pop();
+                blank_lines -= 1;
+                trailing_blank = true;
+            }
+            let more_items = i < lines.len()
+                && leading_spaces(lines[i]) == indent
+                && list_marker(lines[i].trim_start()).is_some_and(|m| m.kind.continues(first.kind));
+            if blank_lines > 0 || (trailing_blank && more_items) {
+                loose = true;
+            }
+            items.push((marker, item));
+        }
+
+        let (open, close) = match first.kind {
+            ListKind::Bullet(_) => ("<ul>".to_string(), "</ul>"),
+            ListKind::Task(_) => ("<ul class=\"task-list\">".to_string(), "</ul>"),
+            ListKind::Ordered(_) => match first.number {
+                Some(n) if n != 1 => (format!("<ol start=\"{}\">", n), "</ol>"),
+                _ => ("<ol>".to_string(), "</ol>"),
+            },
+        };
+
+        let mut html = format!("{}\n", open);
+        for (marker, item) in &items {
+            let content = self.blocks(item, !loose)?;
+            match marker.checked {
+                Some(checked) => html.push_str(&format!(
+                    "<li>\n<input type=\"checkbox\" disabled{}> {}</li>\n",
+                    if checked { " checked" } else { "" },
+                    content
+                )),
+                None => html.push_str(&format!("<li>\n{}</li>\n", content)),
+            }
+        }
+        html.push_str(close);
+        html.push('\n');
+
+        Ok((html, i))
+    }
+
+    fn table(&self, rows: &[&str], attrs: &Attrs) -> String {
+        let mut parsed: Vec<(Vec<&str>, bool)> = Vec::new();
+        let mut alignments: Vec<Option<&str>> = Vec::new();
+
+        for row in rows {
+            let cells = split_cells(row.trim());
+            if cells.iter().all(|c| is_separator_cell(c)) {
+                // The separator turns the row above it into a header
+                alignments = cells.iter().map(|c| alignment(c)).collect();
+                if let Some(last) = parsed.last_mut() {
+                    last.1 = true;
+                }
+                continue;
+            }
+            parsed.push((cells, false));
+        }
+
+        let mut html = format!("<table{}>\n", attrs.html());
+        for (cells, header) in parsed {
+            let tag = if header { "th" } else { "td" };
+            html.push_str("<tr>\n");
+            for (i, cell) in cells.iter().enumerate() {
+                let style = alignments
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .map(|a| format!(" style=\"text-align: {};\"", a))
+                    .unwrap_or_default();
+                html.push_str(&format!("<{0}{1}>{2}</{0}>\n", tag, style, self.inline(cell.trim())));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+        html
+    }
+
+    fn inline(&self, text: &str) -> String {
+        let mut html = String::new();
+        let mut i = 0;
+
+        while let Some(c) = text[i..].chars().next() {
+            let rest = &text[i..];
+            match c {
+                '\\' => {
+                    let next = rest[1..].chars().next();
+                    match next {
+                        Some('\n') => {
+                            html.push_str("<br>\n");
+                            i += 2;
+                        }
+                        Some(' ') => {
+                            html.push_str("&nbsp;");
+                            i += 2;
+                        }
+                        Some(n) if n.is_ascii_punctuation() => {
+                            html.push_str(&escape(&n.to_string()));
+                            i += 2;
+                        }
+                        _ => {
+                            html.push('\\');
+                            i += 1;
+                        }
+                    }
+                }
+                '`' => match code_span(rest) {
+                    Some((code, end)) => {
+                        if let Some(after) = rest[end..].strip_prefix("{=") {
+                            let format_end = after.find('}').unwrap_or(after.len());
+                            if &after[..format_end] == "html" {
+                                html.push_str(code);
+                            }
+                            i += end + 2 + format_end + 1;
+                        } else {
+                            html.push_str(&format!("<code>{}</code>", escape(code)));
+                            i += end;
+                        }
+                    }
+                    None => {
+                        let run = rest.len() - rest.trim_start_matches('`').len();
+                        html.push_str(&rest[..run]);
+                        i += run;
+                    }
+                },
+                '!' if rest[1..].starts_with('[') => match self.link(&rest[1..], true) {
+                    Some((image, end)) => {
+                        html.push_str(&image);
+                        i += 1 + end;
+                    }
+                    None => {
+                        html.push('!');
+                        i += 1;
+                    }
+                },
+                '[' => match self.link(rest, false) {
+                    Some((link, end)) => {
+                        html.push_str(&link);
+                        i += end;
+                    }
+                    None => {
+                        html.push('[');
+                        i += 1;
+                    }
+                },
+                '<' => match autolink(rest) {
+                    Some((link, end)) => {
+                        html.push_str(&link);
+                        i += end;
+                    }
+                    None => {
+                        html.push_str("&lt;");
+                        i += 1;
+                    }
+                },
+                '{' if rest.len() > 1 => {
+                    let marker = rest[1..].chars().next().unwrap();
+                    let tag = match marker {
+                        '=' => Some("mark"),
+                        '+' => Some("ins"),
+                        '-' => Some("del"),
+                        '_' => Some("em"),
+                        '*' => Some("strong"),
+                        _ => None,
+                    };
+                    let closer = format!("{}}}", marker);
+                    match tag.and_then(|tag| find_outside_code(&rest[2..], &closer).map(|end| (tag, end))) {
+                        Some((tag, end)) => {
+                            html.push_str(&format!("<{0}>{1}</{0}>", tag, self.inline(&rest[2..2 + end])));
+                            i += 2 + end + 2;
+                        }
+                        None => {
+                            html.push('{');
+                            i += 1;
+                        }
+                    }
+                }
+                '_' | '*' | '^' | '~' => {
+                    let tag = match c {
+                        '_' => "em",
+                        '*' => "strong",
+                        '^' => "sup",
+                        _ => "sub",
+                    };
+                    match emphasis_end(rest, c) {
+                        Some(end) => {
+                            html.push_str(&format!("<{0}>{1}</{0}>", tag, self.inline(&rest[1..end])));
+                            i += end + 1;
+                        }
+                        None => {
+                            html.push(c);
+                            i += 1;
+                        }
+                    }
+                }
+                _ => {
+                    html.push_str(&escape(&c.to_string()));
+                    i += c.len_utf8();
+                }
+            }
+        }
+
+        html
+    }
+
+    /// A link, image or span at the start of `text`, and its length
+    fn link(&self, text: &str, image: bool) -> Option<(String, usize)> {
+        let close = matching(text, '[', ']')?;
+        let label = &text[1..close];
+        let after = &text[close + 1..];
+
+        let (target, end) = if after.starts_with('(') {
+            let paren = matching(after, '(', ')')?;
+            let url: String = after[1..paren].split_whitespace().collect();
+            (Some(url), close + 1 + paren + 1)
+        } else if after.starts_with('[') {
+            let bracket = after.find(']')?;
+            let reference = match &after[1..bracket] {
+                "" => label,
+                other => other,
+            };
+            let url = self.references.get(&normalize_label(reference))?;
+            (Some(url.clone()), close + 1 + bracket + 1)
+        } else if after.starts_with('{') && !image {
+            (None, close + 1)
+        } else {
+            return None;
+        };
+
+        // Trailing attributes, e.g. `[text]{.note}` or `[text](url){#id}`
+        let mut attrs = Attrs::default();
+        let mut end = end;
+        if text[end..].starts_with('{') {
+            if let Some(brace) = text[end..].find('}') {
+                if let Some(parsed) = Attrs::parse(&text[end..end + brace + 1]) {
+                    attrs = parsed;
+                    end += brace + 1;
+                }
+            }
+        }
+
+        let html = match target {
+            Some(url) if image => format!(
+                "<img alt=\"{}\" src=\"{}\"{}>",
+                escape(&plain_text(label)),
+                escape(&url),
+                attrs.html()
+            ),
+            Some(url) => format!("<a href=\"{}\"{}>{}</a>", escape(&url), attrs.html(), self.inline(label)),
+            None if attrs.is_empty() => return None,
+            None => format!("<span{}>{}</span>", attrs.html(), self.inline(label)),
+        };
+        Some((html, end))
+    }
+}
+
+/// Block or inline attributes: `{#id .class key="value"}`
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Attrs {
+    id: Option<String>,
+    classes: Vec<String>,
+    pairs: Vec<(String, String)>,
+}
+
+impl Attrs {
+    fn parse(text: &str) -> Option<Self> {
+        let inner = text.strip_prefix('{')?.strip_suffix('}')?;
+        let mut attrs = Attrs::default();
+        let mut rest = inner.trim();
+
+        while !rest.is_empty() {
+            if let Some(id) = rest.strip_prefix('#') {
+                let end = id.find(char::is_whitespace).unwrap_or(id.len());
+                attrs.id = Some(id[..end].to_string());
+                rest = id[end..].trim_start();
+            } else if let Some(class) = rest.strip_prefix('.') {
+                let end = class.find(char::is_whitespace).unwrap_or(class.len());
+                attrs.classes.push(class[..end].to_string());
+                rest = class[end..].trim_start();
+            } else {
+                let eq = rest.find('=')?;
+                let key = &rest[..eq];
+                if key.is_empty() || !key.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+                    return None;
+                }
+                let value_start = &rest[eq + 1..];
+                let (value, remaining) = match value_start.strip_prefix('"') {
+                    Some(quoted) => {
+                        let close = quoted.find('"')?;
+                        (&quoted[..close], &quoted[close + 1..])
+                    }
+                    None => {
+                        let end = value_start.find(char::is_whitespace).unwrap_or(value_start.len());
+                        (&value_start[..end], &value_start[end..])
+                    }
+                };
+                attrs.pairs.push((key.to_string(), value.to_string()));
+                rest = remaining.trim_start();
+            }
+        }
+
+        Some(attrs)
+    }
+
+    fn merge(&mut self, other: Attrs) {
+        if other.id.is_some() {
+            self.id = other.id;
+        }
+        self.classes.extend(other.classes);
+        self.pairs.extend(other.pairs);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.id.is_none() && self.classes.is_empty() && self.pairs.is_empty()
+    }
+
+    fn html(&self) -> String {
+        let mut html = attributes(self.id.as_deref(), &self.classes);
+        for (key, value) in &self.pairs {
+            html.push_str(&format!(" {}=\"{}\"", key, escape(value)));
+        }
+        html
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ListKind {
+    Bullet(char),
+    Task(char),
+    /// Delimiter after the number: `.` or `)`
+    Ordered(char),
+}
+
+impl ListKind {
+    /// Whether an item with this marker belongs to a list opened by `first`
+    fn continues(self, first: ListKind) -> bool {
+        match (self, first) {
+            (ListKind::Bullet(a) | ListKind::Task(a), ListKind::Bullet(b) | ListKind::Task(b)) => a == b,
+            _ => self == first,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Marker {
+    kind: ListKind,
+    /// Length of the marker and the space after it
+    width: usize,
+    number: Option<u64>,
+    checked: Option<bool>,
+}
+
+fn list_marker(line: &str) -> Option<Marker> {
+    let first = line.chars().next()?;
+    if matches!(first, '-' | '*' | '+') {
+        let rest = &line[1..];
+        if !(rest.is_empty() || rest.starts_with(' ')) {
+            return None;
+        }
+        let after = rest.strip_prefix(' ').unwrap_or(rest);
+        for (box_text, checked) in [("[ ] ", false), ("[x] ", true), ("[X] ", true)] {
+            if after.starts_with(box_text) {
+                return Some(Marker {
+                    kind: ListKind::Task(first),
+                    width: 2 + box_text.len(),
+                    number: None,
+                    checked: Some(checked),
+                });
+            }
+        }
+        return Some(Marker {
+            kind: ListKind::Bullet(first),
+            width: if rest.is_empty() { 1 } else { 2 },
+            number: None,
+            checked: None,
+        });
+    }
+
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 || digits > 9 {
+        return None;
+    }
+    let delimiter = line[digits..].chars().next()?;
+    let rest = &line[digits + 1..];
+    if !matches!(delimiter, '.' | ')') || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    Some(Marker {
+        kind: ListKind::Ordered(delimiter),
+        width: digits + if rest.is_empty() { 1 } else { 2 },
+        number: line[..digits].parse().ok(),
+        checked: None,
+    })
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.len() - line.trim_start_matches('#').len();
+    let rest = &line[level..];
+    ((1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' '))).then_some(level)
+}
+
+/// Number of backticks and the info string of an opening code fence
+fn code_fence(line: &str) -> Option<(usize, &str)> {
+    let fence = line.len() - line.trim_start_matches('`').len();
+    let info = line[fence..].trim();
+    (fence >= 3 && !info.contains('`')).then_some((fence, info))
+}
+
+fn closes_fence(line: &str, fence: usize) -> bool {
+    line.len() >= fence && line.chars().all(|c| c == '`')
+}
+
+/// Number of colons and the class of a `:::` div fence
+fn div_fence(line: &str) -> Option<(usize, &str)> {
+    let colons = line.len() - line.trim_start_matches(':').len();
+    (colons >= 3).then(|| (colons, line[colons..].trim()))
+}
+
+fn is_thematic_break(line: &str) -> bool {
+    let marks = line.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
+    marks.len() >= 3 && (marks.iter().all(|&c| c == '*') || marks.iter().all(|&c| c == '-'))
+}
+
+fn is_blockquote(line: &str) -> bool {
+    line == ">" || line.starts_with("> ")
+}
+
+fn is_table_row(line: &str) -> bool {
+    let line = line.trim_end();
+    line.len() > 1 && line.starts_with('|') && line.ends_with('|')
+}
+
+/// Cells of a `| a | b |` row; pipes inside code spans or escaped with `\`
+/// don't split
+fn split_cells(row: &str) -> Vec<&str> {
+    let inner = &row[1..row.len() - 1];
+    let mut cells = Vec::new();
+    let mut start = 0;
+    let mut in_code = false;
+    let mut escaped = false;
+
+    for (i, c) in inner.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '`' => in_code = !in_code,
+            '|' if !in_code => {
+                cells.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    cells.push(&inner[start..]);
+    cells
+}
+
+fn is_separator_cell(cell: &str) -> bool {
+    let cell = cell.trim();
+    let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+    !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+}
+
+fn alignment(cell: &str) -> Option<&'static str> {
+    let cell = cell.trim();
+    match (cell.starts_with(':'), cell.ends_with(':')) {
+        (true, true) => Some("center"),
+        (true, false) => Some("left"),
+        (false, true) => Some("right"),
+        (false, false) => None,
+    }
+}
+
+/// Content of the code span opening `text`, and the span's length
+fn code_span(text: &str) -> Option<(&str, usize)> {
+    let ticks = text.len() - text.trim_start_matches('`').len();
+    let body = &text[ticks..];
+    let mut search = 0;
+
+    while let Some(found) = body[search..].find('`') {
+        let start = search + found;
+        let run = body[start..].len() - body[start..].trim_start_matches('`').len();
+        if run == ticks {
+            let code = &body[..start];
+            // A single leading and trailing space lets code start or end
+            // with a backtick
+            let code = match code.strip_prefix(' ').and_then(|c| c.strip_suffix(' ')) {
+                Some(inner) if code.trim().starts_with('`') || code.trim().ends_with('`') => inner,
+                _ => code,
+            };
+            return Some((code, ticks + start + run));
+        }
+        search = start + run;
+    }
+    None
+}
+
+/// Offset of the character closing the emphasis opened by `delimiter` at the
+/// start of `text`. Openers can't be followed by whitespace and closers
+/// can't follow it.
+fn emphasis_end(text: &str, delimiter: char) -> Option<usize> {
+    let after = text[1..].chars().next()?;
+    if after.is_whitespace() || after == delimiter {
+        return None;
+    }
+
+    let mut i = 1;
+    let mut previous = delimiter;
+    while let Some(c) = text[i..].chars().next() {
+        if c == '`' {
+            let (_, end) = code_span(&text[i..])?;
+            i += end;
+            previous = '`';
+            continue;
+        }
+        if c == '\\' {
+            i += 1 + text[i + 1..].chars().next().map_or(0, char::len_utf8);
+            previous = 'x';
+            continue;
+        }
+        if c == delimiter && i > 1 && !previous.is_whitespace() {
+            return Some(i);
+        }
+        previous = c;
+        i += c.len_utf8();
+    }
+    None
+}
+
+/// Offset of `needle` in `text`, ignoring matches inside code spans
+fn find_outside_code(text: &str, needle: &str) -> Option<usize> {
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        if rest.starts_with(needle) {
+            return Some(i);
+        }
+        if rest.starts_with('`') {
+            i += code_span(rest).map_or(1, |(_, end)| end);
+            continue;
+        }
+        i += rest.chars().next().map_or(1, char::len_utf8);
+    }
+    None
+}
+
+/// Offset of the bracket closing the one that opens `text`
+fn matching(text: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        match c {
+            '\\' => {
+                i += 1 + text[i + 1..].chars().next().map_or(0, char::len_utf8);
+                continue;
+            }
+            '`' => {
+                i += code_span(&text[i..]).map_or(1, |(_, end)| end);
+                continue;
+            }
+            _ if c == open => depth += 1,
+            _ if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += c.len_utf8();
+    }
+    None
+}
+
+/// `<https://...>` or `<someone@example.org>`
+fn autolink(text: &str) -> Option<(String, usize)> {
+    let close = text.find('>')?;
+    let target = &text[1..close];
+    if target.is_empty() || target.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let href = if target.contains("://") {
+        target.to_string()
+    } else if target.contains('@') {
+        format!("mailto:{}", target)
+    } else {
+        return None;
+    };
+    Some((format!("<a href=\"{}\">{}</a>", escape(&href), escape(target)), close + 1))
+}
+
+/// `[label]: url` definition lines, keyed by normalized label
+fn reference_definitions(lines: &[&str]) -> HashMap<String, String> {
+    lines
+        .iter()
+        .filter_map(|line| reference_definition(line.trim()))
+        .map(|(label, url)| (normalize_label(label), url.to_string()))
+        .collect()
+}
+
+fn reference_definition(line: &str) -> Option<(&str, &str)> {
+    let close = line.strip_prefix('[')?.find("]:")? + 1;
+    let label = &line[1..close];
+    let url = line[close + 2..].trim();
+    (!label.is_empty() && !label.starts_with('^') && !url.contains(char::is_whitespace)).then_some((label, url))
+}
+
+fn normalize_label(label: &str) -> String {
+    label.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Text of an inline fragment with markup characters dropped, for `alt`
+fn plain_text(text: &str) -> String {
+    text.chars().filter(|c| !matches!(c, '_' | '*' | '`')).collect()
+}
+
+fn leading_spaces(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HighlightConfig;
+
+    fn render(source: &str) -> String {
+        let highlighter = Highlighter::new(&HighlightConfig::default()).unwrap();
+        Djot.render(source, &highlighter).unwrap().html
+    }
+
+    #[test]
+    fn test_djot_blocks() {
+        let html = render(
+            "## Language Policy\n\n{#tools .wide}\n| Tool | Use |\n|:--|--:|\n| `Rust` | CLI \\| systems |\n\n\
+             - one\n- two\n  - nested\n\n1) first\n2) second\n\n- [x] done\n- [ ] todo\n\n\
+             > quoted *text*\n\n::: warning\nCareful\n:::\n\n``` =html\n<video></video>\n```\n\n* * *\n",
+        );
+
+        assert_eq!(
+            html,
+            "<h2>Language Policy</h2>\n\
+             <table id=\"tools\" class=\"wide\">\n<tr>\n<th style=\"text-align: left;\">Tool</th>\n\
+             <th style=\"text-align: right;\">Use</th>\n</tr>\n<tr>\n<td style=\"text-align: left;\"><code>Rust</code></td>\n\
+             <td style=\"text-align: right;\">CLI | systems</td>\n</tr>\n</table>\n\
+             <ul>\n<li>\none\n</li>\n<li>\ntwo\n<ul>\n<li>\nnested\n</li>\n</ul>\n</li>\n</ul>\n\
+             <ol>\n<li>\nfirst\n</li>\n<li>\nsecond\n</li>\n</ol>\n\
+             <ul class=\"task-list\">\n<li>\n<input type=\"checkbox\" disabled checked> done\n</li>\n\
+             <li>\n<input type=\"checkbox\" disabled> todo\n</li>\n</ul>\n\
+             <blockquote>\n<p>quoted <strong>text</strong></p>\n</blockquote>\n\
+             <div class=\"warning\">\n<p>Careful</p>\n</div>\n<video></video>\n<hr>\n"
+        );
+    }
+
+    #[test]
+    fn test_djot_inlines() {
+        let html = render(
+            "_em_ *strong* `a < b` [docs](/docs/){.nav} ![logo](logo.png) <https://wokelang.org>\n\
+             {=marked=} {+added+} {-gone-} x^2^ H~2~O [styled]{.note} [ref][] 2 * 3 * 4\\\nnext\n\n\
+             [ref]: https://example.org\n",
+        );
+
+        assert_eq!(
+            html,
+            "<p><em>em</em> <strong>strong</strong> <code>a &lt; b</code> \
+             <a href=\"/docs/\" class=\"nav\">docs</a> <img alt=\"logo\" src=\"logo.png\"> \
+             <a href=\"https://wokelang.org\">https://wokelang.org</a>\n\
+             <mark>marked</mark> <ins>added</ins> <del>gone</del> x<sup>2</sup> H<sub>2</sub>O \
+             <span class=\"note\">styled</span> <a href=\"https://example.org\">ref</a> 2 * 3 * 4<br>\nnext</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_djot_code_blocks_are_highlighted() {
+        let html = render("``` woke\nto main() {\n    print(\"hi\");\n}\n```\n\n```\nplain <text>\n```\n");
+        assert!(html.starts_with("<pre style="));
+        assert!(html.ends_with("<pre><code>plain &lt;text&gt;\n</code></pre>\n"));
+    }
+
+    #[test]
+    fn test_djot_block_attributes_and_nested_blocks() {
+        let html = render(
+            "{#intro .lead}\nHello\n\n{key=\"v&\" #said}\n> quote\n\n::: note\n- in a div\n:::\n\n\
+             - loose\n\n  ```\n  <b> & \"q\"\n  ```\n- item\n\n  second paragraph\n",
+        );
+
+        assert_eq!(
+            html,
+            "<p id=\"intro\" class=\"lead\">Hello</p>\n\
+             <blockquote id=\"said\" key=\"v&amp;\">\n<p>quote</p>\n</blockquote>\n\
+             <div class=\"note\">\n<ul>\n<li>\nin a div\n</li>\n</ul>\n</div>\n\
+             <ul>\n<li>\n<p>loose</p>\n<pre><code>&lt;b&gt; &amp; &quot;q&quot;\n</code></pre>\n</li>\n\
+             <li>\n<p>item</p>\n<p>second paragraph</p>\n</li>\n</ul>\n"
+        );
+    }
+}
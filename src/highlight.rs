@@ -0,0 +1,169 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Syntax highlighting for code blocks
+
+use anyhow::{anyhow, Context, Result};
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::{
+    css_for_theme_with_class_style, highlighted_html_for_string, ClassStyle, ClassedHTMLGenerator,
+};
+use syntect::parsing::{SyntaxDefinition, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+use crate::config::{HighlightConfig, HighlightStyle};
+use crate::formats;
+
+/// Grammar for `.woke` sources, which syntect doesn't ship
+const WOKELANG_SYNTAX: &str = include_str!("../syntaxes/wokelang.sublime-syntax");
+
+/// Prefix for generated classes, so they can't clash with site styles
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+/// Syntaxes and themes, loaded once per build and shared by every page
+pub struct Highlighter {
+    syntax_set: SyntaxSet,
+    theme_set: ThemeSet,
+    config: HighlightConfig,
+}
+
+impl Highlighter {
+    pub fn new(config: &HighlightConfig) -> Result<Self> {
+        let wokelang = SyntaxDefinition::load_from_str(WOKELANG_SYNTAX, true, None)
+            .with_context(|| "Failed to load WokeLang syntax definition")?;
+
+        let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+        builder.add(wokelang);
+
+        let highlighter = Self {
+            syntax_set: builder.build(),
+            theme_set: ThemeSet::load_defaults(),
+            config: config.clone(),
+        };
+
+        // Fail early on a misspelt theme rather than on the first code block
+        match config.style {
+            HighlightStyle::Inline => {
+                highlighter.theme(&config.theme)?;
+            }
+            HighlightStyle::Classes => {
+                highlighter.theme(&config.light_theme)?;
+                highlighter.theme(&config.dark_theme)?;
+            }
+        }
+
+        Ok(highlighter)
+    }
+
+    pub fn highlight(&self, code: &str, language: &str) -> Result<String> {
+        let syntax = self
+            .syntax_set
+            .find_syntax_by_token(language)
+            .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
+
+        match self.config.style {
+            HighlightStyle::Inline => {
+                highlighted_html_for_string(code, &self.syntax_set, syntax, self.theme(&self.config.theme)?)
+                    .with_context(|| format!("Failed to highlight {} code", language))
+            }
+            HighlightStyle::Classes => {
+                let mut generator =
+                    ClassedHTMLGenerator::new_with_class_style(syntax, &self.syntax_set, CLASS_STYLE);
+                for line in LinesWithEndings::from(code) {
+                    generator
+                        .parse_html_for_line_which_includes_newline(line)
+                        .with_context(|| format!("Failed to highlight {} code", language))?;
+                }
+
+                Ok(format!(
+                    "<pre class=\"hl-code\"><code class=\"language-{}\">{}</code></pre>\n",
+                    formats::escape(language),
+                    generator.finalize()
+                ))
+            }
+        }
+    }
+
+    /// `syntax-light.css` and `syntax-dark.css` for class-based output.
+    ///
+    /// Templates pick between them with `prefers-color-scheme`; there are no
+    /// stylesheets when highlighting uses inline styles.
+    pub fn stylesheets(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        if self.config.style != HighlightStyle::Classes {
+            return Ok(Vec::new());
+        }
+
+        let mut stylesheets = Vec::new();
+        for (file, theme) in [
+            ("syntax-light.css", &self.config.light_theme),
+            ("syntax-dark.css", &self.config.dark_theme),
+        ] {
+            let css = css_for_theme_with_class_style(self.theme(theme)?, CLASS_STYLE)
+                .with_context(|| format!("Failed to generate stylesheet for theme '{}'", theme))?;
+            stylesheets.push((file.to_string(), css.into_bytes()));
+        }
+
+        Ok(stylesheets)
+    }
+
+    fn theme(&self, name: &str) -> Result<&Theme> {
+        self.theme_set.themes.get(name).ok_or_else(|| {
+            let mut available: Vec<&str> = self.theme_set.themes.keys().map(String::as_str).collect();
+            available.sort();
+            anyhow!(
+                "Unknown highlight theme '{}' (available: {})",
+                name,
+                available.join(", ")
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_wokelang() {
+        let highlighter = Highlighter::new(&HighlightConfig::default()).unwrap();
+        let html = highlighter
+            .highlight("to main() {\n    remember x = 1;\n    give back x;\n}\n", "woke")
+            .unwrap();
+
+        assert!(html.starts_with("<pre"));
+        assert!(html.contains(">to </span>"));
+        assert!(html.contains(">give back</span>"));
+    }
+
+    #[test]
+    fn test_class_based_highlighting() {
+        let config = HighlightConfig {
+            style: HighlightStyle::Classes,
+            ..Default::default()
+        };
+        let highlighter = Highlighter::new(&config).unwrap();
+        let html = highlighter.highlight("give back 1;\n", "woke").unwrap();
+
+        assert!(html.starts_with("<pre class=\"hl-code\"><code class=\"language-woke\">"));
+        assert!(html.contains("hl-keyword hl-control hl-return hl-woke"));
+        assert!(!html.contains("style="));
+
+        let stylesheets = highlighter.stylesheets().unwrap();
+        assert_eq!(stylesheets[0].0, "syntax-light.css");
+        assert_eq!(stylesheets[1].0, "syntax-dark.css");
+        assert!(String::from_utf8_lossy(&stylesheets[0].1).contains(".hl-code"));
+        assert_ne!(stylesheets[0].1, stylesheets[1].1);
+
+        let html = highlighter.highlight("x\n", "x\"><script>").unwrap();
+        assert!(html.starts_with("<pre class=\"hl-code\"><code class=\"language-x&quot;&gt;&lt;script&gt;\">"));
+    }
+
+    #[test]
+    fn test_unknown_theme_is_rejected() {
+        let config = HighlightConfig {
+            theme: "no-such-theme".to_string(),
+            ..Default::default()
+        };
+        assert!(Highlighter::new(&config).is_err());
+    }
+}
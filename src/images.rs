@@ -0,0 +1,463 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Responsive images
+//!
+//! PNG, JPEG and WebP images are resized to a set of widths, never beyond
+//! their own, and re-encoded in extra formats such as WebP and AVIF, all in
+//! pure Rust. Every variant is written to `processed_images/` under a name
+//! derived from the hash of the source and the parameters, so a variant
+//! already in the output is reused until either changes. The build cache
+//! records the variants each page refers to and removes those no page
+//! refers to any more.
+//!
+//! Templates call `responsive_image(path=...)`. With `images.process_content`
+//! set, `<img>` tags in content that point at bundled or static images are
+//! rewritten the same way, with `srcset`, `sizes`, `width` and `height`.
+
+use anyhow::{bail, Context, Result};
+use image::codecs::avif::AvifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageReader};
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use walkdir::WalkDir;
+
+use crate::bundles;
+use crate::cache;
+use crate::config::{Config, ImageFormat, ImagesConfig};
+use crate::content::Page;
+
+/// Output directory for resized and converted images
+const OUTPUT_DIR: &str = "processed_images";
+
+/// Number of hex digits of the SHA-256 kept in variant names
+const HASH_LEN: usize = 16;
+
+/// AVIF encoder speed from 1 (smallest) to 10 (fastest)
+const AVIF_SPEED: u8 = 8;
+
+pub struct ImageProcessor {
+    config: ImagesConfig,
+    static_dir: PathBuf,
+    content_dir: PathBuf,
+    output_path: PathBuf,
+}
+
+/// Everything needed for an `<img>`, plus `<source>`s for other formats
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResponsiveImage {
+    /// Full-size variant in the source's own format
+    pub src: String,
+    /// Variants in the source's own format
+    pub srcset: String,
+    pub sizes: String,
+    pub width: u32,
+    pub height: u32,
+    /// Variants in the configured formats, best first
+    pub sources: Vec<ImageSource>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImageSource {
+    #[serde(rename = "type")]
+    pub mime: &'static str,
+    pub srcset: String,
+}
+
+impl ImageProcessor {
+    pub fn new(config: &Config, content_dir: &Path, output_path: &Path) -> Self {
+        Self {
+            config: config.images.clone(),
+            static_dir: PathBuf::from(&config.build.static_dir),
+            content_dir: content_dir.to_path_buf(),
+            output_path: output_path.to_path_buf(),
+        }
+    }
+
+    /// Write the variants of `source` that aren't in the output yet and
+    /// describe them all
+    pub fn process(&self, source: &Path, widths: &[u32], formats: &[ImageFormat], sizes: &str) -> Result<ResponsiveImage> {
+        let bytes = fs::read(source).with_context(|| format!("Failed to read image {}", source.display()))?;
+        let Some(own_format) = format_of(source) else {
+            bail!("Can't process {}: only PNG, JPEG and WebP images are supported", source.display());
+        };
+        let (width, height) = ImageReader::new(Cursor::new(&bytes))
+            .with_guessed_format()?
+            .into_dimensions()
+            .with_context(|| format!("Failed to read image {}", source.display()))?;
+
+        let mut widths: Vec<u32> = widths.iter().copied().filter(|&w| w > 0 && w < width).collect();
+        widths.push(width);
+        widths.sort_unstable();
+        widths.dedup();
+
+        let source_hash = cache::hash_bytes(&bytes);
+        let stem = source.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+        let mut decoded: Option<DynamicImage> = None;
+        let mut variants = |format: ImageFormat| -> Result<String> {
+            let mut srcset = Vec::new();
+            for &w in &widths {
+                let params = format!("{}:{:?}:{}:{}", source_hash, format, w, self.config.quality);
+                let name = format!("{}.{}.{}.{}", stem, &cache::hash_bytes(params.as_bytes())[..HASH_LEN], w, extension(format));
+                let output = self.output_path.join(OUTPUT_DIR).join(&name);
+
+                if !output.exists() {
+                    let encoded = if format == own_format && w == width {
+                        bytes.clone()
+                    } else {
+                        if decoded.is_none() {
+                            let image = image::load_from_memory(&bytes)
+                                .with_context(|| format!("Failed to decode image {}", source.display()))?;
+                            decoded = Some(image);
+                        }
+                        let image = decoded.as_ref().unwrap();
+                        let h = ((u64::from(height) * u64::from(w) + u64::from(width) / 2) / u64::from(width)).max(1);
+                        let resized = image.resize_exact(w, h as u32, FilterType::Lanczos3);
+                        encode(&resized, format, self.config.quality)
+                            .with_context(|| format!("Failed to encode {} as {:?}", source.display(), format))?
+                    };
+                    write_atomically(&output, &encoded)?;
+                }
+                srcset.push(format!("/{}/{} {}w", OUTPUT_DIR, name, w));
+            }
+            Ok(srcset.join(", "))
+        };
+
+        let sources = formats
+            .iter()
+            .filter(|&&format| format != own_format)
+            .map(|&format| {
+                Ok(ImageSource {
+                    mime: mime_type(format),
+                    srcset: variants(format)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let srcset = variants(own_format)?;
+        let src = srcset
+            .rsplit(", ")
+            .next()
+            .and_then(|last| last.split(' ').next())
+            .unwrap_or_default()
+            .to_string();
+
+        Ok(ResponsiveImage {
+            src,
+            srcset,
+            sizes: sizes.to_string(),
+            width,
+            height,
+            sources,
+        })
+    }
+
+    /// Make every `<img>` in a page's body and summary that shows one of its
+    /// bundled files or a static image responsive
+    pub fn rewrite_page(&self, page: &mut Page) -> Result<()> {
+        page.html = self.rewrite_images(&page.html, page)?;
+        if let Some(summary) = &page.summary {
+            page.summary = Some(self.rewrite_images(summary, page)?);
+        }
+        Ok(())
+    }
+
+    fn rewrite_images(&self, html: &str, page: &Page) -> Result<String> {
+        static IMG: OnceLock<Regex> = OnceLock::new();
+        static SRC: OnceLock<Regex> = OnceLock::new();
+        let img = IMG.get_or_init(|| Regex::new(r"<img\s([^>]*?)\s*(/?)>").unwrap());
+        let src = SRC.get_or_init(|| Regex::new(r#"\bsrc="([^"]*)"\s*"#).unwrap());
+
+        let mut error = None;
+        let html = img.replace_all(html, |caps: &Captures| {
+            let attributes = &caps[1];
+            let source = src
+                .captures(attributes)
+                .filter(|_| !attributes.contains("srcset="))
+                .and_then(|path| self.content_image(page, &path[1]));
+            let Some(source) = source else {
+                return caps[0].to_string();
+            };
+
+            let config = &self.config;
+            match self.process(&source, &config.widths, &config.formats, &config.sizes) {
+                Ok(image) => to_html(&image, src.replace(attributes, "").trim(), &caps[2]),
+                Err(e) => {
+                    error.get_or_insert(e);
+                    caps[0].to_string()
+                }
+            }
+        });
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(html.into_owned()),
+        }
+    }
+
+    /// Source of an image a page shows, if it can be processed
+    fn content_image(&self, page: &Page, path: &str) -> Option<PathBuf> {
+        let source = bundles::asset_source(&self.content_dir, page, path).or_else(|| {
+            let file = self.static_dir.join(path.strip_prefix('/')?);
+            file.is_file().then_some(file)
+        })?;
+        format_of(&source).map(|_| source)
+    }
+
+    /// Source of an image named in a template: `@/` for content, or else a
+    /// path in the static directory
+    fn template_image(&self, path: &str) -> PathBuf {
+        match path.strip_prefix("@/") {
+            Some(path) => self.content_dir.join(path),
+            None => self.static_dir.join(path.trim_start_matches('/')),
+        }
+    }
+}
+
+/// Output paths of the variants `html` refers to, without duplicates
+pub fn variants_in(html: &str) -> Vec<String> {
+    static VARIANT: OnceLock<Regex> = OnceLock::new();
+    let re = VARIANT.get_or_init(|| Regex::new(&format!(r#"/({}/[^\s"',]+)"#, OUTPUT_DIR)).unwrap());
+
+    let mut variants: Vec<String> = re.captures_iter(html).map(|c| c[1].to_string()).collect();
+    variants.sort();
+    variants.dedup();
+    variants
+}
+
+/// Hash of every image that can be processed. Templates calling
+/// `responsive_image()` embed variant names derived from these, so an edited
+/// image has to re-render their pages.
+pub fn sources_hash(dirs: &[&Path]) -> Result<String> {
+    let mut hashes = String::new();
+    for dir in dirs.iter().filter(|dir| dir.exists()) {
+        for entry in WalkDir::new(dir).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+            if entry.file_type().is_file() && format_of(entry.path()).is_some() {
+                hashes.push_str(&entry.path().to_string_lossy());
+                hashes.push_str(&cache::hash_file(entry.path())?);
+            }
+        }
+    }
+    Ok(cache::hash_bytes(hashes.as_bytes()))
+}
+
+/// `<picture>` with a `<source>` per extra format around the `<img>`, or
+/// just the `<img>` when there are none
+pub fn to_html(image: &ResponsiveImage, attributes: &str, closing: &str) -> String {
+    let mut img = format!(
+        "<img src=\"{}\" srcset=\"{}\" sizes=\"{}\" width=\"{}\" height=\"{}\"",
+        image.src, image.srcset, image.sizes, image.width, image.height
+    );
+    if !attributes.is_empty() {
+        img.push(' ');
+        img.push_str(attributes);
+    }
+    img.push_str(if closing.is_empty() { ">" } else { " />" });
+
+    if image.sources.is_empty() {
+        return img;
+    }
+    let sources: String = image
+        .sources
+        .iter()
+        .map(|s| format!("<source type=\"{}\" srcset=\"{}\" sizes=\"{}\">", s.mime, s.srcset, image.sizes))
+        .collect();
+    format!("<picture>{}{}</picture>", sources, img)
+}
+
+/// Tera function `responsive_image(path="shots/playground.png")`, optionally
+/// with `widths`, `formats` and `sizes` overriding the `images` config
+pub struct ResponsiveImageFunction {
+    processor: Arc<ImageProcessor>,
+}
+
+impl ResponsiveImageFunction {
+    pub fn new(processor: Arc<ImageProcessor>) -> Self {
+        Self { processor }
+    }
+}
+
+impl tera::Function for ResponsiveImageFunction {
+    fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+        let config = &self.processor.config;
+        let path = args
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| tera::Error::msg("responsive_image() requires a string `path` argument"))?;
+        let widths: Vec<u32> = match args.get("widths") {
+            Some(widths) => tera::from_value(widths.clone())?,
+            None => config.widths.clone(),
+        };
+        let formats: Vec<ImageFormat> = match args.get("formats") {
+            Some(formats) => tera::from_value(formats.clone())?,
+            None => config.formats.clone(),
+        };
+        let sizes = args.get("sizes").and_then(|v| v.as_str()).unwrap_or(&config.sizes);
+
+        let image = self
+            .processor
+            .process(&self.processor.template_image(path), &widths, &formats, sizes)
+            .map_err(|e| tera::Error::msg(format!("responsive_image(): {:#}", e)))?;
+        Ok(tera::to_value(image)?)
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+fn format_of(path: &Path) -> Option<ImageFormat> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "png" => Some(ImageFormat::Png),
+        "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
+        "webp" => Some(ImageFormat::Webp),
+        _ => None,
+    }
+}
+
+fn extension(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Avif => "avif",
+        ImageFormat::Webp => "webp",
+        ImageFormat::Jpeg => "jpg",
+        ImageFormat::Png => "png",
+    }
+}
+
+fn mime_type(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Avif => "image/avif",
+        ImageFormat::Webp => "image/webp",
+        ImageFormat::Jpeg => "image/jpeg",
+        ImageFormat::Png => "image/png",
+    }
+}
+
+fn encode(image: &DynamicImage, format: ImageFormat, quality: u8) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    match format {
+        ImageFormat::Jpeg => DynamicImage::from(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, quality))?,
+        ImageFormat::Png => image.write_with_encoder(PngEncoder::new(&mut bytes))?,
+        ImageFormat::Webp => DynamicImage::from(image.to_rgba8())
+            .write_with_encoder(WebPEncoder::new_lossless(&mut bytes))?,
+        ImageFormat::Avif => DynamicImage::from(image.to_rgba8())
+            .write_with_encoder(AvifEncoder::new_with_speed_quality(&mut bytes, AVIF_SPEED, quality))?,
+    }
+    Ok(bytes)
+}
+
+/// Pages are processed in parallel and may share an image, so a variant is
+/// only ever seen complete
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temporary = path.with_extension(format!("{:?}.tmp", std::thread::current().id()).replace(['(', ')'], ""));
+    fs::write(&temporary, bytes).with_context(|| format!("Failed to write {}", temporary.display()))?;
+    fs::rename(&temporary, path).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn test_process_resizes_converts_and_reuses_variants() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("static/shots/playground.png");
+        fs::create_dir_all(source.parent().unwrap()).unwrap();
+        RgbImage::from_fn(64, 30, |x, _| Rgb([x as u8 * 4, 0, 0])).save(&source).unwrap();
+
+        let mut config = Config::default_wokelang();
+        config.build.static_dir = dir.path().join("static").to_string_lossy().into_owned();
+        let output = dir.path().join("public");
+        let processor = ImageProcessor::new(&config, &dir.path().join("content"), &output);
+
+        let image = processor
+            .process(&processor.template_image("/shots/playground.png"), &[16, 32, 128], &[ImageFormat::Webp], "50vw")
+            .unwrap();
+        assert_eq!((image.width, image.height), (64, 30));
+        assert_eq!(image.sources.len(), 1);
+        assert_eq!(image.sources[0].mime, "image/webp");
+
+        let widths: Vec<&str> = image.srcset.split(", ").map(|v| v.rsplit(' ').next().unwrap()).collect();
+        assert_eq!(widths, ["16w", "32w", "64w"]);
+        assert!(image.src.starts_with("/processed_images/playground.") && image.src.ends_with(".64.png"));
+        assert_eq!(fs::read(output.join(&image.src[1..])).unwrap(), fs::read(&source).unwrap());
+
+        let small = image.srcset.split(' ').next().unwrap();
+        assert_eq!(image::image_dimensions(output.join(&small[1..])).unwrap(), (16, 8));
+        let webp = image.sources[0].srcset.split(' ').next().unwrap();
+        assert_eq!(image::image_dimensions(output.join(&webp[1..])).unwrap(), (16, 8));
+
+        // Existing variants are reused; changed parameters make new ones
+        fs::write(output.join(&small[1..]), "kept").unwrap();
+        let again = processor.process(&source, &[16, 32, 128], &[ImageFormat::Webp], "50vw").unwrap();
+        assert_eq!(again, image);
+        assert_eq!(fs::read_to_string(output.join(&small[1..])).unwrap(), "kept");
+
+        let mut lower = ImageProcessor::new(&config, &dir.path().join("content"), &output);
+        lower.config.quality = 50;
+        let changed = lower.process(&source, &[16], &[], "50vw").unwrap();
+        assert_ne!(changed.srcset.split(' ').next().unwrap(), small);
+    }
+
+    #[test]
+    fn test_to_html() {
+        let image = ResponsiveImage {
+            src: "/p/a.64.png".to_string(),
+            srcset: "/p/a.32.png 32w, /p/a.64.png 64w".to_string(),
+            sizes: "100vw".to_string(),
+            width: 64,
+            height: 30,
+            sources: vec![ImageSource {
+                mime: "image/webp",
+                srcset: "/p/a.32.webp 32w".to_string(),
+            }],
+        };
+        assert_eq!(
+            to_html(&image, "alt=\"Shot\"", "/"),
+            "<picture><source type=\"image/webp\" srcset=\"/p/a.32.webp 32w\" sizes=\"100vw\">\
+             <img src=\"/p/a.64.png\" srcset=\"/p/a.32.png 32w, /p/a.64.png 64w\" sizes=\"100vw\" \
+             width=\"64\" height=\"30\" alt=\"Shot\" /></picture>"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_page_makes_bundled_images_responsive() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = dir.path().join("content");
+        let cover = content.join("blog/launch/cover.png");
+        fs::create_dir_all(cover.parent().unwrap()).unwrap();
+        RgbImage::from_fn(40, 20, |x, _| Rgb([x as u8 * 6, 0, 0])).save(&cover).unwrap();
+
+        let mut config = Config::default_wokelang();
+        config.images.widths = vec![20];
+        config.images.formats = Vec::new();
+        let processor = ImageProcessor::new(&config, &content, &dir.path().join("public"));
+
+        let html = "<p><img src=\"/blog/launch/cover.png\" alt=\"Cover\" /></p>\n\
+                    <p><img src=\"https://example.org/x.png\" alt=\"Remote\" /></p>\n";
+        let mut page = Page::new(serde_yaml::from_str("title: Launch").unwrap(), "", html.to_string(), String::new());
+        page.relative_path = PathBuf::from("blog/launch/index.md");
+        page.output_path = PathBuf::from("blog/launch/index.html");
+        page.assets = vec!["cover.png".to_string()];
+        processor.rewrite_page(&mut page).unwrap();
+
+        assert!(page.html.contains(".20.png 20w, /processed_images/cover."), "{}", page.html);
+        assert!(page.html.contains("sizes=\"100vw\" width=\"40\" height=\"20\" alt=\"Cover\" />"), "{}", page.html);
+        assert!(page.html.contains("<img src=\"https://example.org/x.png\" alt=\"Remote\" />"));
+    }
+}
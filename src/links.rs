@@ -0,0 +1,356 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Internal links
+//!
+//! Content links to other content by source file rather than by URL, either
+//! from the content root as `@/docs/getting-started.md#install` or relative
+//! to the linking page as `../docs/getting-started.md`. Once every page has
+//! been placed, such links are rewritten to the target's site path, which
+//! works under `serve` as well as on the live site. Site-absolute links like
+//! `/docs/getting-started/` and same-page `#fragment` links are checked too.
+//! A link to anything the build doesn't publish, or to a fragment the target
+//! has no id for, fails the build, and every broken link is reported at once.
+
+use anyhow::{bail, Result};
+use regex::{Captures, Regex};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::cache;
+use crate::content::Page;
+use crate::formats;
+use crate::sections::Section;
+
+/// A page or section that links can point at
+struct Target {
+    path: String,
+    ids: HashSet<String>,
+}
+
+/// Everything a link can resolve to
+struct Targets<'a> {
+    /// Pages and sections by source key
+    files: HashMap<String, Target>,
+    /// Source keys of pages and sections by site path
+    paths: HashMap<String, String>,
+    /// Site paths of everything else the build publishes, such as static
+    /// assets, feeds and taxonomy listings
+    published: &'a HashSet<String>,
+}
+
+/// Rewrite internal links in every page and section body, failing with a
+/// per-file report if any of them don't resolve. `published` holds the site
+/// paths of everything built besides pages and sections.
+pub fn resolve(
+    source_path: &Path,
+    pages: &mut [Page],
+    sections: &mut [Section],
+    published: &HashSet<String>,
+) -> Result<()> {
+    let mut targets = Targets {
+        files: HashMap::new(),
+        paths: HashMap::new(),
+        published,
+    };
+    for page in pages.iter() {
+        targets.add(cache::path_key(&page.relative_path), Target::new(&page.path, &page.html));
+    }
+    for section in sections.iter() {
+        targets.add(cache::path_key(&section.relative_path), Target::new(&section.path, &section.html));
+    }
+
+    let mut report = Vec::new();
+    let mut broken = 0;
+    let mut note = |relative: &Path, problems: Vec<String>| {
+        if !problems.is_empty() {
+            broken += problems.len();
+            report.push(format!("  {}", source_path.join(relative).display()));
+            report.extend(problems.iter().map(|p| format!("    - {}", p)));
+        }
+    };
+
+    for page in pages.iter_mut() {
+        let from = cache::path_key(&page.relative_path);
+        let mut problems = Vec::new();
+        page.html = rewrite(&page.html, &from, &targets, &mut problems);
+        if let Some(summary) = &page.summary {
+            // The summary repeats the start of the body, so its problems
+            // have already been noted
+            page.summary = Some(rewrite(summary, &from, &targets, &mut Vec::new()));
+        }
+        note(&page.relative_path, problems);
+    }
+    for section in sections.iter_mut() {
+        let from = cache::path_key(&section.relative_path);
+        let mut problems = Vec::new();
+        section.html = rewrite(&section.html, &from, &targets, &mut problems);
+        note(&section.relative_path, problems);
+    }
+
+    if broken > 0 {
+        bail!("{} internal link(s) don't resolve:\n{}", broken, report.join("\n"));
+    }
+    Ok(())
+}
+
+impl Target {
+    fn new(path: &str, html: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            ids: anchor_ids(html),
+        }
+    }
+}
+
+impl Targets<'_> {
+    fn add(&mut self, key: String, target: Target) {
+        self.paths.insert(target.path.clone(), key.clone());
+        self.files.insert(key, target);
+    }
+
+    /// Source key of the page or section published at site path `path`,
+    /// `Some(None)` for any other published file. Paths may leave off the
+    /// trailing `/` or `.html`, or spell out `index.html`.
+    fn find(&self, path: &str) -> Option<Option<&str>> {
+        let mut candidates = vec![path.to_string()];
+        if let Some(dir) = path.strip_suffix("index.html") {
+            candidates.push(dir.to_string());
+        }
+        if !path.ends_with('/') {
+            candidates.push(format!("{}/", path));
+            candidates.push(format!("{}.html", path));
+        }
+
+        candidates.iter().find_map(|candidate| match self.paths.get(candidate) {
+            Some(key) => Some(Some(key.as_str())),
+            None => self.published.contains(candidate).then_some(None),
+        })
+    }
+}
+
+/// Rewrite the `href`s in `html` of the file at source key `from`
+fn rewrite(html: &str, from: &str, targets: &Targets, problems: &mut Vec<String>) -> String {
+    static HREF: OnceLock<Regex> = OnceLock::new();
+    let re = HREF.get_or_init(|| Regex::new(r#"(\s)href="([^"]*)""#).unwrap());
+
+    re.replace_all(html, |caps: &Captures| match resolve_link(&formats::unescape(&caps[2]), from, targets) {
+        Some(Ok(path)) => format!("{}href=\"{}\"", &caps[1], formats::escape(&path)),
+        Some(Err(problem)) => {
+            problems.push(problem);
+            caps[0].to_string()
+        }
+        None => caps[0].to_string(),
+    })
+    .into_owned()
+}
+
+/// Site path for an internal link, or what's wrong with it. `None` when
+/// the link isn't to the site at all.
+fn resolve_link(link: &str, from: &str, targets: &Targets) -> Option<Result<String, String>> {
+    let (file, fragment) = match link.split_once('#') {
+        Some((file, fragment)) => (file, Some(fragment)),
+        None => (link, None),
+    };
+
+    let key = if file.is_empty() {
+        // A bare `#` is usually a script hook rather than a link
+        fragment.filter(|f| !f.is_empty())?;
+        from.to_string()
+    } else if let Some(key) = file.strip_prefix("@/") {
+        key.to_string()
+    } else if is_relative_content_link(file) {
+        let dir = from.rsplit_once('/').map_or("", |(dir, _)| dir);
+        match join(dir, file) {
+            Some(key) => key,
+            None => return Some(Err(format!("{}: points outside the content directory", link))),
+        }
+    } else if file.starts_with('/') && !file.starts_with("//") {
+        let path = file.split('?').next().unwrap_or_default();
+        match targets.find(path) {
+            Some(Some(key)) => key.to_string(),
+            // Fragments of static files can't be checked
+            Some(None) => return Some(Ok(link.to_string())),
+            None => return Some(Err(format!("{}: nothing is published at {}", link, path))),
+        }
+    } else {
+        return None;
+    };
+
+    let Some(target) = targets.files.get(&key) else {
+        return Some(Err(format!("{}: no page or section at {}", link, key)));
+    };
+    Some(match fragment {
+        Some(fragment) if !fragment.is_empty() && !target.ids.contains(fragment) => {
+            Err(format!("{}: {} has no anchor #{}", link, key, fragment))
+        }
+        // Links that already name a URL keep it as written
+        _ if file.is_empty() || file.starts_with('/') => Ok(link.to_string()),
+        Some(fragment) => Ok(format!("{}#{}", target.path, fragment)),
+        None => Ok(target.path.clone()),
+    })
+}
+
+/// A link like `guide.md` or `../docs/intro.adoc`: no scheme, not absolute,
+/// and naming a file in one of the content formats
+fn is_relative_content_link(file: &str) -> bool {
+    let has_scheme = file
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.is_empty() && !scheme.contains('/'));
+    !file.is_empty() && !file.starts_with('/') && !has_scheme && formats::for_path(Path::new(file)).is_some()
+}
+
+/// Every `id` and `name` attribute in `html`, which `#fragment` links can
+/// point at
+pub fn anchor_ids(html: &str) -> HashSet<String> {
+    static ID: OnceLock<Regex> = OnceLock::new();
+    let re = ID.get_or_init(|| Regex::new(r#"\s(?:id|name)="([^"]*)""#).unwrap());
+    re.captures_iter(html).map(|c| formats::unescape(&c[1])).collect()
+}
+
+/// `relative` resolved against `dir`, both `/`-separated; `None` if it
+/// climbs above the root
+pub fn join(dir: &str, relative: &str) -> Option<String> {
+    let mut parts: Vec<&str> = dir.split('/').filter(|p| !p.is_empty()).collect();
+    for part in relative.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(published: &HashSet<String>) -> Targets<'_> {
+        let mut targets = Targets {
+            files: HashMap::new(),
+            paths: HashMap::new(),
+            published,
+        };
+        targets.add(
+            "docs/getting-started.md".to_string(),
+            Target::new("/docs/getting-started/", r#"<h2 id="install">Install</h2>"#),
+        );
+        targets.add("docs/_index.md".to_string(), Target::new("/docs/", ""));
+        targets.add("about.djot".to_string(), Target::new("/about/", ""));
+        targets.add(
+            "blog/post.md".to_string(),
+            Target::new("/blog/post.html", r#"<p id="fn-1">Note</p><p data-id="fn-3">Not an anchor</p>"#),
+        );
+        targets
+    }
+
+    #[test]
+    fn test_resolve_links() {
+        let published = HashSet::from(["/feed.xml".to_string()]);
+        let targets = targets(&published);
+        let resolve = |link: &str| resolve_link(link, "blog/post.md", &targets);
+
+        assert_eq!(resolve("@/docs/getting-started.md#install"), Some(Ok("/docs/getting-started/#install".into())));
+        assert_eq!(resolve("@/docs/_index.md"), Some(Ok("/docs/".into())));
+        assert_eq!(resolve("../docs/getting-started.md"), Some(Ok("/docs/getting-started/".into())));
+        assert_eq!(resolve("./../about.djot"), Some(Ok("/about/".into())));
+        assert_eq!(resolve("/docs/getting-started/"), Some(Ok("/docs/getting-started/".into())));
+        assert_eq!(resolve("/docs/getting-started#install"), Some(Ok("/docs/getting-started#install".into())));
+        assert_eq!(resolve("/blog/post?ref=feed"), Some(Ok("/blog/post?ref=feed".into())));
+        assert_eq!(resolve("/feed.xml"), Some(Ok("/feed.xml".into())));
+        assert_eq!(resolve("#fn-1"), Some(Ok("#fn-1".into())));
+
+        assert_eq!(resolve("https://example.org/README.md"), None);
+        assert_eq!(resolve("//example.org/"), None);
+        assert_eq!(resolve("#"), None);
+        assert_eq!(resolve("logo.png"), None);
+
+        assert_eq!(
+            resolve("@/docs/missing.md"),
+            Some(Err("@/docs/missing.md: no page or section at docs/missing.md".into()))
+        );
+        assert_eq!(
+            resolve("../docs/getting-started.md#setup"),
+            Some(Err("../docs/getting-started.md#setup: docs/getting-started.md has no anchor #setup".into()))
+        );
+        assert_eq!(
+            resolve("../../outside.md"),
+            Some(Err("../../outside.md: points outside the content directory".into()))
+        );
+        assert_eq!(
+            resolve("/docs/old-page/"),
+            Some(Err("/docs/old-page/: nothing is published at /docs/old-page/".into()))
+        );
+        assert_eq!(
+            resolve("/docs/#intro"),
+            Some(Err("/docs/#intro: docs/_index.md has no anchor #intro".into()))
+        );
+        assert_eq!(resolve("#fn-2"), Some(Err("#fn-2: blog/post.md has no anchor #fn-2".into())));
+        assert_eq!(resolve("#fn-3"), Some(Err("#fn-3: blog/post.md has no anchor #fn-3".into())));
+    }
+
+    #[test]
+    fn test_escaped_hrefs_are_unescaped_for_lookup() {
+        let published = HashSet::new();
+        let targets = targets(&published);
+        let mut problems = Vec::new();
+        let html = rewrite(
+            r#"<a href="/blog/post.html?a=1&amp;b=2">x</a> <a href="/nope?a=1&amp;b=2">y</a> <a data-href="/z">z</a>"#,
+            "docs/_index.md",
+            &targets,
+            &mut problems,
+        );
+
+        assert_eq!(
+            html,
+            r#"<a href="/blog/post.html?a=1&amp;b=2">x</a> <a href="/nope?a=1&amp;b=2">y</a> <a data-href="/z">z</a>"#
+        );
+        assert_eq!(problems, ["/nope?a=1&b=2: nothing is published at /nope"]);
+    }
+
+    #[test]
+    fn test_resolve_rewrites_pages_and_reports_every_broken_link() {
+        let page = |relative: &str, path: &str, html: &str| {
+            let mut page = Page::new(serde_yaml::from_str("title: X").unwrap(), "", html.to_string(), String::new());
+            page.relative_path = relative.into();
+            page.path = path.to_string();
+            page
+        };
+        let mut pages = vec![
+            page("docs/getting-started.md", "/docs/getting-started.html", r#"<h2 id="install">Install</h2>"#),
+            page(
+                "blog/post.md",
+                "/blog/post.html",
+                concat!(
+                    r#"<a href="@/docs/getting-started.md#install">Install</a> "#,
+                    r#"<a href="../docs/getting-started.md">start</a>"#,
+                ),
+            ),
+        ];
+        pages[1].summary = Some(r#"<a href="@/docs/getting-started.md#install">Install</a>"#.to_string());
+        let published = HashSet::from(["/feed.xml".to_string()]);
+
+        resolve(Path::new("content"), &mut pages, &mut [], &published).unwrap();
+        assert_eq!(
+            pages[1].html,
+            r#"<a href="/docs/getting-started.html#install">Install</a> <a href="/docs/getting-started.html">start</a>"#
+        );
+        assert_eq!(pages[1].summary.as_deref(), Some(r#"<a href="/docs/getting-started.html#install">Install</a>"#));
+
+        pages[1].html = r#"<a href="@/docs/getting-started.md#setup">Setup</a> <a href="missing.md">gone</a>
+                           <a href="/feed.xml">feed</a> <a href="/docs/old/">old</a>"#
+            .to_string();
+        let err = resolve(Path::new("content"), &mut pages, &mut [], &published).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "3 internal link(s) don't resolve:\n  content/blog/post.md\n    \
+             - @/docs/getting-started.md#setup: docs/getting-started.md has no anchor #setup\n    \
+             - missing.md: no page or section at blog/missing.md\n    \
+             - /docs/old/: nothing is published at /docs/old/"
+        );
+    }
+}
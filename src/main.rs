@@ -5,22 +5,50 @@
 //!
 //! A Rust-based SSG for wokelang.org, featuring:
 //! - Frontmatter parsing (YAML)
-//! - Markdown to HTML conversion
-//! - Template system
+//! - Markdown, Djot and AsciiDoc content with syntax highlighting (including WokeLang)
+//! - Template system, with shortcodes for reusable components in content
+//! - Data files (YAML, JSON, TOML, CSV) available to templates
+//! - Heading anchors and nested tables of contents
+//! - Internal links by source file, checked at build time
+//! - A `check` command for broken links, anchors and orphans in the built site
 //! - Asset pipeline with hash-based cache busting
-//! - RSS/Atom feeds
+//! - Page bundles keeping images and attachments next to their content
+//! - Responsive images resized and converted to WebP/AVIF
+//! - Incremental, parallel builds backed by a content-hash cache
+//! - Taxonomies such as tags and categories, with per-term listings
+//! - RSS/Atom feeds, site-wide and per taxonomy term
 //! - Sitemap generation
 //! - Live reload for development
 
+mod assets;
 mod builder;
+mod bundles;
+mod cache;
+mod check;
+mod collections;
 mod config;
 mod content;
+mod data;
 mod feeds;
+mod formats;
+mod highlight;
+mod images;
+mod links;
+mod pagination;
+mod sections;
 mod server;
+mod shortcodes;
+mod site;
 mod sitemap;
+mod taxonomies;
 mod templates;
+#[cfg(test)]
+mod testing;
+mod toc;
+mod watcher;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
+use std::path::Path;
 use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
@@ -47,6 +75,12 @@ enum Commands {
         /// Output directory
         #[arg(short, long, default_value = "public")]
         output: String,
+        /// Include draft pages
+        #[arg(long)]
+        drafts: bool,
+        /// Include pages dated in the future or past their expiry date
+        #[arg(long)]
+        future: bool,
     },
     /// Serve the site with live reload
     Serve {
@@ -59,6 +93,21 @@ enum Commands {
         /// Server port
         #[arg(short, long, default_value = "3000")]
         port: u16,
+        /// Include draft pages
+        #[arg(long)]
+        drafts: bool,
+        /// Include pages dated in the future or past their expiry date
+        #[arg(long)]
+        future: bool,
+    },
+    /// Check the built site for broken links, missing anchors and orphan pages
+    Check {
+        /// Output directory of a previous build
+        #[arg(short, long, default_value = "public")]
+        output: String,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
     },
 }
 
@@ -71,20 +120,41 @@ async fn main() -> Result<()> {
             println!("Initializing new site at: {}", path);
             builder::init_site(&path)?;
         }
-        Commands::Build { source, output } => {
+        Commands::Build {
+            source,
+            output,
+            drafts,
+            future,
+        } => {
             println!("Building site from {} to {}", source, output);
             let config = config::Config::load("config.yaml")?;
-            builder::build_site(&config, &source, &output)?;
+            let options = builder::BuildOptions { drafts, future };
+            builder::build_site(&config, &source, &output, &options)?;
         }
         Commands::Serve {
             source,
             output,
             port,
+            drafts,
+            future,
         } => {
             println!("Building and serving site on port {}", port);
             let config = config::Config::load("config.yaml")?;
-            builder::build_site(&config, &source, &output)?;
-            server::serve(&output, port).await?;
+            let options = builder::BuildOptions { drafts, future };
+            builder::build_site(&config, &source, &output, &options)?;
+            server::serve("config.yaml", &source, &output, port, options).await?;
+        }
+        Commands::Check { output, json } => {
+            let config = config::Config::load("config.yaml")?;
+            let report = check::check(&config, Path::new(&output))?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", report.to_human());
+            }
+            if report.failed() {
+                bail!("{} broken link(s) in {}", report.broken.len() + report.external.len(), output);
+            }
         }
     }
 
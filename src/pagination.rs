@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Pagination for long listings
+//!
+//! A section with `paginate_by` in its front matter, or a taxonomy with
+//! `paginate_by` in its config, splits its listing across pagers: the first
+//! at the listing's own URL, the rest at `page/2/`, `page/3/` and so on
+//! beneath it. `page/1/` is kept as an alias that points back to the first.
+
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Directory under a listing that holds its later pagers
+const PAGER_DIR: &str = "page";
+
+#[derive(Debug, Clone)]
+pub struct Paginator {
+    /// Site path of the first pager, ending in `/`
+    base: String,
+    pub paginate_by: usize,
+    /// Number of pages being split up
+    pub total_pages: usize,
+    /// Number of pagers, at least one so an empty listing still renders
+    pub number_pagers: usize,
+}
+
+impl Paginator {
+    /// `None` unless `paginate_by` asks for pagination
+    pub fn new(base: &str, total_pages: usize, paginate_by: Option<usize>) -> Option<Self> {
+        let paginate_by = paginate_by.filter(|&n| n > 0)?;
+        Some(Self {
+            base: base.to_string(),
+            paginate_by,
+            total_pages,
+            number_pagers: total_pages.div_ceil(paginate_by).max(1),
+        })
+    }
+
+    /// Pagers in order, numbered from 1
+    pub fn pagers(&self) -> Range<usize> {
+        1..self.number_pagers + 1
+    }
+
+    /// Site path of pager `index`
+    pub fn path(&self, index: usize) -> String {
+        if index == 1 {
+            self.base.clone()
+        } else {
+            format!("{}{}/{}/", self.base, PAGER_DIR, index)
+        }
+    }
+
+    /// Rendered file of pager `index`, relative to the output directory
+    pub fn output_path(&self, index: usize) -> PathBuf {
+        output_file(&self.path(index))
+    }
+
+    /// `page/1/` under the listing, which only redirects to the first pager
+    pub fn alias_path(&self) -> String {
+        format!("{}{}/1/", self.base, PAGER_DIR)
+    }
+
+    pub fn alias_output_path(&self) -> PathBuf {
+        output_file(&self.alias_path())
+    }
+
+    /// Positions in the full listing shown on pager `index`
+    pub fn range(&self, index: usize) -> Range<usize> {
+        let start = ((index - 1) * self.paginate_by).min(self.total_pages);
+        start..(start + self.paginate_by).min(self.total_pages)
+    }
+}
+
+/// Minimal page sending browsers and crawlers on to `permalink`
+pub fn alias_html(permalink: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Redirecting</title>\n\
+         <link rel=\"canonical\" href=\"{0}\">\n<meta http-equiv=\"refresh\" content=\"0; url={0}\">\n\
+         </head>\n<body>\n<a href=\"{0}\">{0}</a>\n</body>\n</html>\n",
+        permalink
+    )
+}
+
+fn output_file(path: &str) -> PathBuf {
+    PathBuf::from(path.trim_start_matches('/')).join("index.html")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pager_paths_and_ranges() {
+        let paginator = Paginator::new("/blog/", 7, Some(3)).unwrap();
+        assert_eq!(paginator.pagers(), 1..4);
+        assert_eq!(paginator.path(1), "/blog/");
+        assert_eq!(paginator.path(3), "/blog/page/3/");
+        assert_eq!(paginator.output_path(2), PathBuf::from("blog/page/2/index.html"));
+        assert_eq!(paginator.alias_path(), "/blog/page/1/");
+        assert_eq!(paginator.range(1), 0..3);
+        assert_eq!(paginator.range(3), 6..7);
+
+        assert!(Paginator::new("/blog/", 7, None).is_none());
+        assert!(Paginator::new("/blog/", 7, Some(0)).is_none());
+        assert_eq!(Paginator::new("/", 0, Some(5)).unwrap().number_pagers, 1);
+    }
+}
@@ -0,0 +1,352 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Content sections
+//!
+//! A directory containing an `_index` file, in any content format, is a
+//! section. Its front matter controls how the section lists its pages, and
+//! every page belongs to the nearest section above it, which gives templates
+//! a hierarchy to build listings, breadcrumbs and previous/next links from.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::cache;
+use crate::config::{warn_unknown_keys, AnchorLinks, UnknownKeys};
+use crate::content::{parse_front_matter, Page};
+use crate::formats;
+use crate::highlight::Highlighter;
+use crate::pagination::Paginator;
+use crate::shortcodes;
+use crate::templates::Templates;
+use crate::toc::{self, Heading};
+
+/// File stem that turns its directory into a section
+const SECTION_STEM: &str = "_index";
+
+/// Whether `path` is a section's `_index` file in one of the content formats
+pub fn is_section_file(path: &Path) -> bool {
+    path.file_stem().is_some_and(|stem| stem == SECTION_STEM) && formats::for_path(path).is_some()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SectionFrontMatter {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default = "default_section_template")]
+    pub template: String,
+    /// Order of `section.pages`
+    #[serde(default)]
+    pub sort_by: SortBy,
+    /// Position among sibling sections
+    #[serde(default)]
+    pub weight: i64,
+    /// Split the listing into pagers of this many pages
+    #[serde(default)]
+    pub paginate_by: Option<usize>,
+    /// Free-form values for templates, available as `section.extra`
+    #[serde(default)]
+    pub extra: BTreeMap<String, serde_yaml::Value>,
+    #[serde(flatten, skip_serializing)]
+    pub unknown: UnknownKeys,
+}
+
+impl Default for SectionFrontMatter {
+    fn default() -> Self {
+        Self {
+            title: String::new(),
+            description: String::new(),
+            template: default_section_template(),
+            sort_by: SortBy::default(),
+            weight: 0,
+            paginate_by: None,
+            extra: BTreeMap::new(),
+            unknown: UnknownKeys::new(),
+        }
+    }
+}
+
+fn default_section_template() -> String {
+    "section.html".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    /// Source file order
+    #[default]
+    Path,
+    /// Newest first, undated pages last
+    Date,
+    Title,
+    /// Lowest `weight` first
+    Weight,
+}
+
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub front_matter: SectionFrontMatter,
+    pub html: String,
+    pub toc: Vec<Heading>,
+    /// Directory relative to the content root, `/`-separated, empty for the root
+    pub dir: String,
+    /// The `_index` file's path relative to the content directory
+    pub relative_path: PathBuf,
+    pub output_path: PathBuf,
+    pub path: String,
+    pub permalink: String,
+    pub parent: Option<usize>,
+    /// Enclosing sections from the root down to `parent`
+    pub ancestors: Vec<usize>,
+    /// Pages directly in this section, as sorted page indices
+    pub pages: Vec<usize>,
+    /// Child sections ordered by weight, then path
+    pub subsections: Vec<usize>,
+}
+
+impl Section {
+    pub fn from_file(
+        path: &Path,
+        highlighter: &Highlighter,
+        templates: &Templates,
+        anchors: AnchorLinks,
+    ) -> Result<Self> {
+        let format = formats::for_path(path)
+            .with_context(|| format!("Unsupported content format: {}", path.display()))?;
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+
+        let (mut front_matter, source) = parse_front_matter::<SectionFrontMatter>(&content)?;
+        warn_unknown_keys(&path.display().to_string(), &front_matter.unknown);
+        let expanded = shortcodes::expand(&source, format, highlighter, templates)?;
+        let rendered = format.render(&expanded.source, highlighter)?;
+        let html = expanded.restore(&rendered.html);
+        if front_matter.title.is_empty() {
+            if let Some(title) = rendered.title {
+                front_matter.title = title;
+            }
+        }
+        let (html, toc) = toc::link_headings(&html, anchors);
+        Ok(Self::new(front_matter, html, toc))
+    }
+
+    /// A section that hasn't been placed in the site yet
+    pub fn new(front_matter: SectionFrontMatter, html: String, toc: Vec<Heading>) -> Self {
+        Self {
+            front_matter,
+            html,
+            toc,
+            dir: String::new(),
+            relative_path: PathBuf::new(),
+            output_path: PathBuf::new(),
+            path: String::new(),
+            permalink: String::new(),
+            parent: None,
+            ancestors: Vec::new(),
+            pages: Vec::new(),
+            subsections: Vec::new(),
+        }
+    }
+
+    pub fn paginator(&self) -> Option<Paginator> {
+        Paginator::new(&self.path, self.pages.len(), self.front_matter.paginate_by)
+    }
+}
+
+/// Connect pages and sections into a tree.
+///
+/// Both slices must already have their paths assigned. Fills in each
+/// section's pages and subsections, and each page's parent, ancestors and
+/// previous/next siblings.
+pub fn link(sections: &mut [Section], pages: &mut [Page]) {
+    // Parents first, so ancestor chains can be copied from the parent
+    let mut order: Vec<usize> = (0..sections.len()).collect();
+    order.sort_by_key(|&i| depth(&sections[i].dir));
+
+    for &i in &order {
+        let parent = nearest_section(sections, parent_dir(&sections[i].dir));
+        sections[i].parent = parent;
+        if let Some(p) = parent {
+            let mut ancestors = sections[p].ancestors.clone();
+            ancestors.push(p);
+            sections[i].ancestors = ancestors;
+            sections[p].subsections.push(i);
+        }
+    }
+
+    for (i, page) in pages.iter_mut().enumerate() {
+        let dir = page_dir(page);
+        if let Some(s) = nearest_section(sections, Some(dir.as_str())) {
+            let mut ancestors = sections[s].ancestors.clone();
+            ancestors.push(s);
+            page.parent = Some(s);
+            page.ancestors = ancestors;
+            sections[s].pages.push(i);
+        }
+    }
+
+    for i in 0..sections.len() {
+        let sort_by = sections[i].front_matter.sort_by;
+        let mut members = std::mem::take(&mut sections[i].pages);
+        members.sort_by(|&a, &b| compare_pages(&pages[a], &pages[b], sort_by));
+
+        for (position, &page) in members.iter().enumerate() {
+            pages[page].previous = position.checked_sub(1).map(|p| members[p]);
+            pages[page].next = members.get(position + 1).copied();
+        }
+        sections[i].pages = members;
+
+        let mut subsections = std::mem::take(&mut sections[i].subsections);
+        subsections.sort_by(|&a, &b| {
+            let (a, b) = (&sections[a], &sections[b]);
+            a.front_matter.weight.cmp(&b.front_matter.weight).then_with(|| a.dir.cmp(&b.dir))
+        });
+        sections[i].subsections = subsections;
+    }
+}
+
+/// Order of two pages in a listing sorted by `sort_by`
+pub fn compare_pages(a: &Page, b: &Page, sort_by: SortBy) -> Ordering {
+    let by_path = || a.relative_path.cmp(&b.relative_path);
+    match sort_by {
+        SortBy::Path => by_path(),
+        // `Some` sorts after `None`, so compare in reverse to put undated last
+        SortBy::Date => b.front_matter.date.cmp(&a.front_matter.date).then_with(by_path),
+        SortBy::Title => a.front_matter.title.cmp(&b.front_matter.title).then_with(by_path),
+        SortBy::Weight => a.front_matter.weight.cmp(&b.front_matter.weight).then_with(by_path),
+    }
+}
+
+/// Directory a page is listed under. A directory `index.md` stands for its
+/// directory, so it belongs to the section above it rather than to itself.
+fn page_dir(page: &Page) -> String {
+    let dir = cache::path_key(page.relative_path.parent().unwrap_or(Path::new("")));
+    let is_index = page.relative_path.file_stem().and_then(|s| s.to_str()) == Some("index");
+    if is_index {
+        parent_dir(&dir).unwrap_or_default().to_string()
+    } else {
+        dir
+    }
+}
+
+/// Closest section at or above `dir`
+fn nearest_section(sections: &[Section], mut dir: Option<&str>) -> Option<usize> {
+    while let Some(current) = dir {
+        if let Some(i) = sections.iter().position(|s| s.dir == current) {
+            return Some(i);
+        }
+        dir = parent_dir(current);
+    }
+    None
+}
+
+fn parent_dir(dir: &str) -> Option<&str> {
+    if dir.is_empty() {
+        return None;
+    }
+    Some(dir.rfind('/').map_or("", |i| &dir[..i]))
+}
+
+fn depth(dir: &str) -> usize {
+    if dir.is_empty() {
+        0
+    } else {
+        dir.matches('/').count() + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestSite;
+
+    fn page(relative: &str, front_matter: &str) -> Page {
+        let mut page = Page::new(serde_yaml::from_str(front_matter).unwrap(), "", String::new(), String::new());
+        page.relative_path = PathBuf::from(relative);
+        page
+    }
+
+    fn section(dir: &str, front_matter: &str) -> Section {
+        let mut section = Section::new(serde_yaml::from_str(front_matter).unwrap(), String::new(), Vec::new());
+        section.dir = dir.to_string();
+        section
+    }
+
+    #[test]
+    fn test_link_builds_the_tree() {
+        let mut sections = vec![
+            section("docs/advanced", "title: Advanced\nweight: 2"),
+            section("docs", "title: Docs\nsort_by: weight"),
+            section("", "title: Home"),
+            section("docs/reference", "title: Reference\nweight: 1"),
+        ];
+        let mut pages = vec![
+            page("docs/basics.md", "title: Basics\nweight: 2"),
+            page("docs/intro.md", "title: Intro\nweight: 1"),
+            page("docs/tutorial/index.md", "title: Tutorial\nweight: 3"),
+            page("docs/advanced/macros.md", "title: Macros"),
+            page("docs/advanced/deep/traits.md", "title: Traits"),
+            page("about.md", "title: About"),
+        ];
+        link(&mut sections, &mut pages);
+
+        let (advanced, docs, home, reference) = (0, 1, 2, 3);
+        assert_eq!(sections[home].subsections, [docs]);
+        assert_eq!(sections[docs].subsections, [reference, advanced]);
+        assert_eq!(sections[advanced].ancestors, [home, docs]);
+        assert_eq!(sections[advanced].parent, Some(docs));
+        assert_eq!(sections[home].parent, None);
+
+        // A directory index belongs to the section above its directory
+        assert_eq!(sections[docs].pages, [1, 0, 2]);
+        assert_eq!(sections[advanced].pages, [4, 3]);
+        assert_eq!(sections[home].pages, [5]);
+        assert_eq!(pages[4].ancestors, [home, docs, advanced]);
+
+        assert_eq!((pages[1].previous, pages[1].next), (None, Some(0)));
+        assert_eq!((pages[0].previous, pages[0].next), (Some(1), Some(2)));
+        assert_eq!((pages[2].previous, pages[2].next), (Some(0), None));
+        assert_eq!((pages[5].previous, pages[5].next), (None, None));
+    }
+
+    #[test]
+    fn test_compare_pages() {
+        let old = page("b.md", "title: Zebra\ndate: 2025-01-01T00:00:00Z\nweight: 1");
+        let new = page("c.md", "title: Apple\ndate: 2026-01-01T00:00:00Z\nweight: 1");
+        let undated = page("a.md", "title: Mango\nweight: 0");
+
+        let sorted = |sort_by: SortBy| {
+            let mut pages = [&old, &new, &undated];
+            pages.sort_by(|a, b| compare_pages(a, b, sort_by));
+            pages.map(|p| p.front_matter.title.as_str())
+        };
+        assert_eq!(sorted(SortBy::Path), ["Mango", "Zebra", "Apple"]);
+        assert_eq!(sorted(SortBy::Date), ["Apple", "Zebra", "Mango"]);
+        assert_eq!(sorted(SortBy::Title), ["Apple", "Mango", "Zebra"]);
+        assert_eq!(sorted(SortBy::Weight), ["Mango", "Zebra", "Apple"]);
+    }
+
+    #[test]
+    fn test_sections_in_every_format() {
+        let site = TestSite::new();
+        site.write("content/docs/_index.adoc", "= Documentation\n\nNOTE: Read *this*.\n");
+        site.write("content/blog/_index.djot", "---\ntitle: Blog\n---\n_News_\n");
+
+        let docs = site.section("docs/_index.adoc").unwrap();
+        assert_eq!(docs.front_matter.title, "Documentation");
+        assert_eq!(
+            docs.html,
+            "<div class=\"admonition note\">\n<p class=\"admonition-title\">Note</p>\n\
+             <p>Read <strong>this</strong>.</p>\n</div>\n"
+        );
+
+        let blog = site.section("blog/_index.djot").unwrap();
+        assert_eq!(blog.front_matter.title, "Blog");
+        assert_eq!(blog.html, "<p><em>News</em></p>\n");
+    }
+}
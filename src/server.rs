@@ -10,19 +10,32 @@
 use tower_http::trace::TraceLayer;
 use tower_livereload::LiveReloadLayer;
 
-pub async fn serve(output_dir: &str, port: u16) -> Result<()> {
+use crate::builder::BuildOptions;
+use crate::watcher;
+
+pub async fn serve(
+    config_path: &str,
+    source: &str,
+    output_dir: &str,
+    port: u16,
+    options: BuildOptions,
+) -> Result<()> {
     let addr = SocketAddr::from(([127, 0, 0, 1], port));
 
     let serve_dir = ServeDir::new(output_dir);
+    let livereload = LiveReloadLayer::new();
+
+    // Held for the lifetime of the server; dropping it stops rebuilds
+    let _watcher = watcher::watch(config_path, source, output_dir, options, livereload.reloader())?;
 
     let app = Router::new()
         .fallback_service(get_service(serve_dir))
-        .layer(LiveReloadLayer::new())
+        .layer(livereload)
         .layer(TraceLayer::new_for_http());
 
     println!("🚀 Server running at http://127.0.0.1:{}", port);
     println!("   Serving: {}", output_dir);
-    println!("   Live reload enabled");
+    println!("   Live reload enabled, watching for changes");
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
     axum::serve(listener, app).await?;
@@ -0,0 +1,518 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Shortcodes
+//!
+//! Reusable components inside content: `{{ name(key=value) }}` inline, or
+//! `{% name(key=value) %}body{% end %}` around a body. Each renders
+//! `templates/shortcodes/<name>.html` with its arguments, `body` and `nth`
+//! (how many times the shortcode has been used on the page so far). A body
+//! is rendered in the page's own format first, and may hold shortcodes of
+//! its own, block ones included.
+//!
+//! Shortcodes are expanded before the content format sees the source, but
+//! their HTML is swapped in only after rendering, so the format never
+//! mangles it. Code is left alone: fenced and indented code blocks, raw
+//! `<pre>` blocks and inline code, which keeps documentation about
+//! shortcodes readable. The same pass finds the summary marker, so a marker
+//! shown in a code sample doesn't cut the summary short.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+
+use crate::content::{html_to_text, SUMMARY_MARKER};
+use crate::formats::ContentFormat;
+use crate::highlight::Highlighter;
+use crate::templates::Templates;
+
+/// Source with every shortcode replaced by a placeholder, and the HTML that
+/// goes back in its place
+pub struct Expanded {
+    pub source: String,
+    /// Where the first summary marker outside code was in `source`, which
+    /// no longer contains it
+    pub summary_end: Option<usize>,
+    rendered: Vec<String>,
+}
+
+impl Expanded {
+    /// Put the rendered shortcodes into HTML produced from `source`. A
+    /// shortcode that ended up alone in a paragraph replaces the paragraph.
+    pub fn restore(&self, html: &str) -> String {
+        restore(html, &self.rendered)
+    }
+
+    /// `source` with the text of each shortcode in place of its
+    /// placeholder, for counting words
+    pub fn text(&self) -> String {
+        let mut text = self.source.clone();
+        for (i, rendered) in self.rendered.iter().enumerate() {
+            text = text.replace(&placeholder(i), &html_to_text(rendered));
+        }
+        text
+    }
+}
+
+fn restore(html: &str, rendered: &[String]) -> String {
+    let mut html = html.to_string();
+    for (i, rendered) in rendered.iter().enumerate() {
+        let placeholder = placeholder(i);
+        if html.contains(&placeholder) {
+            let paragraph = format!("<p>{}</p>", placeholder);
+            html = html.replace(&paragraph, rendered.trim_end()).replace(&placeholder, rendered);
+        }
+    }
+    html
+}
+
+/// Placeholders use private-use characters, which no markup language treats
+/// as syntax and no author types
+fn placeholder(index: usize) -> String {
+    format!("\u{E000}shortcode{}\u{E001}", index)
+}
+
+/// Render every shortcode in `source` through `templates`, rendering block
+/// bodies as `format`
+pub fn expand(
+    source: &str,
+    format: &dyn ContentFormat,
+    highlighter: &Highlighter,
+    templates: &Templates,
+) -> Result<Expanded> {
+    let mut expander = Expander {
+        format,
+        highlighter,
+        templates,
+        uses: HashMap::new(),
+        rendered: Vec::new(),
+        summary_end: None,
+    };
+    let (source, _, _) = expander.expand(source, false)?;
+    Ok(Expanded {
+        source,
+        summary_end: expander.summary_end,
+        rendered: expander.rendered,
+    })
+}
+
+struct Expander<'a> {
+    format: &'a dyn ContentFormat,
+    highlighter: &'a Highlighter,
+    templates: &'a Templates,
+    uses: HashMap<String, usize>,
+    /// HTML of every shortcode so far, by placeholder number
+    rendered: Vec<String>,
+    summary_end: Option<usize>,
+}
+
+/// Kind of code block being copied through untouched
+enum Code {
+    /// Fenced, with its opening fence
+    Fence(String),
+    /// Indented by four spaces or a tab, after a blank line
+    Indented,
+    /// Raw HTML `<pre>` up to its `</pre>`
+    Pre,
+}
+
+impl Expander<'_> {
+    /// Expand `source` up to its end or, inside a block shortcode, up to the
+    /// `{% end %}` closing the block. Returns the expanded source, how much
+    /// of `source` was used and whether the closing `{% end %}` was found.
+    fn expand(&mut self, source: &str, in_block: bool) -> Result<(String, usize, bool)> {
+        let mut output = String::with_capacity(source.len());
+        let mut code: Option<Code> = None;
+        let mut line_start = !in_block;
+        let mut previous_blank = true;
+        let mut in_list = false;
+        let mut pos = 0;
+
+        while pos < source.len() {
+            let rest = &source[pos..];
+
+            // Code blocks are copied through line by line
+            if line_start {
+                let line_end = rest.find('\n').map_or(rest.len(), |i| i + 1);
+                let raw = rest[..line_end].trim_end_matches(['\n', '\r']);
+                let line = raw.trim();
+                let blank = line.is_empty();
+                let indented = raw.starts_with("    ") || raw.starts_with('\t');
+
+                let in_code = match &code {
+                    Some(Code::Fence(open)) => {
+                        if closes_fence(line, open) {
+                            code = None;
+                        }
+                        true
+                    }
+                    Some(Code::Pre) => {
+                        if line.contains("</pre>") {
+                            code = None;
+                        }
+                        true
+                    }
+                    Some(Code::Indented) if blank || indented => true,
+                    _ => {
+                        code = if let Some(fence) = code_fence(line) {
+                            Some(Code::Fence(fence))
+                        } else if opens_pre(line) {
+                            (!line.contains("</pre>")).then_some(Code::Pre)
+                        } else if indented && !blank && previous_blank && !in_list {
+                            Some(Code::Indented)
+                        } else {
+                            None
+                        };
+                        code.is_some() || opens_pre(line)
+                    }
+                };
+
+                previous_blank = blank;
+                if is_list_item(line) {
+                    in_list = true;
+                } else if !blank && !indented {
+                    in_list = false;
+                }
+                if in_code {
+                    output.push_str(&rest[..line_end]);
+                    pos += line_end;
+                    continue;
+                }
+            }
+
+            // Inline code runs to the next run of as many backticks, within
+            // the paragraph
+            if rest.starts_with('`') {
+                let ticks = rest.len() - rest.trim_start_matches('`').len();
+                let paragraph = rest.find("\n\n").unwrap_or(rest.len());
+                let closer = "`".repeat(ticks);
+                let len = rest[ticks..paragraph]
+                    .find(&closer)
+                    .map_or(ticks, |end| ticks + end + ticks);
+                output.push_str(&rest[..len]);
+                line_start = rest[..len].ends_with('\n');
+                pos += len;
+                continue;
+            }
+
+            if in_block {
+                if let Some(len) = parse_end(rest) {
+                    return Ok((output, pos + len, true));
+                }
+            } else if self.summary_end.is_none() && rest.starts_with(SUMMARY_MARKER) {
+                self.summary_end = Some(output.len());
+                line_start = false;
+                pos += SUMMARY_MARKER.len();
+                continue;
+            }
+
+            if let Some(call) = parse_call(rest) {
+                let nth = self.uses.entry(call.name.to_string()).or_insert(0);
+                *nth += 1;
+                let nth = *nth;
+
+                let (body, len) = if call.block {
+                    let (body, body_len, closed) = self.expand(&rest[call.len..], true)?;
+                    if !closed {
+                        bail!("Shortcode '{}' is missing its {{% end %}}", call.name);
+                    }
+                    let html = self
+                        .format
+                        .render(body.trim_matches('\n'), self.highlighter)
+                        .with_context(|| format!("Failed to render the body of shortcode '{}'", call.name))?
+                        .html;
+                    (Some(restore(&html, &self.rendered)), call.len + body_len)
+                } else {
+                    (None, call.len)
+                };
+
+                let html = self
+                    .templates
+                    .render_shortcode(call.name, &call.args, body.as_deref().map(str::trim_end), nth)
+                    .with_context(|| format!("Failed to render shortcode '{}'", call.name))?;
+                output.push_str(&placeholder(self.rendered.len()));
+                self.rendered.push(html);
+                line_start = false;
+                pos += len;
+                continue;
+            }
+
+            let c = rest.chars().next().unwrap_or_default();
+            output.push(c);
+            line_start = c == '\n';
+            pos += c.len_utf8();
+        }
+
+        Ok((output, pos, false))
+    }
+}
+
+/// Opening fence of a code block: backticks or tildes in Markdown and Djot,
+/// `----` and `....` in AsciiDoc
+fn code_fence(line: &str) -> Option<String> {
+    for mark in ['`', '~'] {
+        let run = line.len() - line.trim_start_matches(mark).len();
+        if run >= 3 {
+            return Some(line[..run].to_string());
+        }
+    }
+    ["----", "...."]
+        .into_iter()
+        .find(|delimiter| line == *delimiter)
+        .map(String::from)
+}
+
+fn closes_fence(line: &str, open: &str) -> bool {
+    line.starts_with(open) && line.chars().all(|c| open.starts_with(c))
+}
+
+struct Call<'a> {
+    name: &'a str,
+    args: Map<String, Value>,
+    block: bool,
+    /// Length of the `{{ ... }}` or `{% ... %}` tag
+    len: usize,
+}
+
+/// A shortcode tag at the start of `text`. Anything that doesn't look
+/// exactly like one is left as text.
+fn parse_call(text: &str) -> Option<Call<'_>> {
+    let (block, close) = if text.starts_with("{{") {
+        (false, "}}")
+    } else if text.starts_with("{%") {
+        (true, "%}")
+    } else {
+        return None;
+    };
+
+    let inner = text[2..].trim_start();
+    let name_len = inner
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+        .unwrap_or(inner.len());
+    let name = &inner[..name_len];
+    if name.is_empty() || name == "end" || !inner[name_len..].starts_with('(') {
+        return None;
+    }
+
+    let args_start = text.len() - inner.len() + name_len + 1;
+    let (args, args_len) = parse_args(&text[args_start..])?;
+    let after = &text[args_start + args_len..];
+    let tail = after.trim_start();
+    if !tail.starts_with(close) {
+        return None;
+    }
+
+    Some(Call {
+        name,
+        args,
+        block,
+        len: text.len() - tail.len() + close.len(),
+    })
+}
+
+/// Length of a `{% end %}` tag at the start of `text`
+fn parse_end(text: &str) -> Option<usize> {
+    let inner = text.strip_prefix("{%")?.trim_start();
+    let after = inner.strip_prefix("end")?.trim_start();
+    after.starts_with("%}").then(|| text.len() - after.len() + 2)
+}
+
+/// Opening tag of a raw HTML `<pre>` block
+fn opens_pre(line: &str) -> bool {
+    line.strip_prefix("<pre").is_some_and(|rest| rest.starts_with(['>', ' ', '\t']))
+}
+
+/// `- item`, `* item`, `+ item`, `1. item` or `1) item`
+fn is_list_item(line: &str) -> bool {
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let marker = match digits {
+        0 => line.strip_prefix(['-', '*', '+']),
+        _ => line[digits..].strip_prefix(['.', ')']),
+    };
+    marker.is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']))
+}
+
+/// `key=value, ...)` up to and including the closing parenthesis
+fn parse_args(text: &str) -> Option<(Map<String, Value>, usize)> {
+    let mut args = Map::new();
+    let mut rest = text.trim_start();
+
+    loop {
+        if let Some(after) = rest.strip_prefix(')') {
+            return Some((args, text.len() - after.len()));
+        }
+        let key_len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))?;
+        let key = &rest[..key_len];
+        if key.is_empty() {
+            return None;
+        }
+        rest = rest[key_len..].trim_start().strip_prefix('=')?.trim_start();
+
+        let (value, len) = parse_value(rest)?;
+        args.insert(key.to_string(), value);
+        rest = rest[len..].trim_start();
+        if let Some(after) = rest.strip_prefix(',') {
+            rest = after.trim_start();
+        }
+    }
+}
+
+/// A string, number, boolean or list literal and its length
+fn parse_value(text: &str) -> Option<(Value, usize)> {
+    let first = text.chars().next()?;
+    match first {
+        '"' | '\'' => {
+            let mut value = String::new();
+            let mut chars = text.char_indices().skip(1);
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => value.push(chars.next()?.1),
+                    _ if c == first => return Some((Value::String(value), i + 1)),
+                    _ => value.push(c),
+                }
+            }
+            None
+        }
+        '[' => {
+            let mut items = Vec::new();
+            let mut rest = text[1..].trim_start();
+            loop {
+                if let Some(after) = rest.strip_prefix(']') {
+                    return Some((Value::Array(items), text.len() - after.len()));
+                }
+                let (item, len) = parse_value(rest)?;
+                items.push(item);
+                rest = rest[len..].trim_start();
+                if let Some(after) = rest.strip_prefix(',') {
+                    rest = after.trim_start();
+                }
+            }
+        }
+        _ => {
+            let len = text
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+'))
+                .unwrap_or(text.len());
+            let literal = &text[..len];
+            let value = match literal {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ => match literal.parse::<i64>() {
+                    Ok(n) => Value::Number(n.into()),
+                    Err(_) => Value::Number(Number::from_f64(literal.parse().ok()?)?),
+                },
+            };
+            Some((value, len))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::Markdown;
+    use crate::testing::{highlighter, TestSite};
+
+    fn templates(files: &[(&str, &str)]) -> (TestSite, Templates) {
+        let site = TestSite::new();
+        for (name, source) in files {
+            site.write(&format!("templates/shortcodes/{}", name), source);
+        }
+        let templates = site.templates();
+        (site, templates)
+    }
+
+    /// Expand and render Markdown the way pages do
+    fn render(source: &str, templates: &Templates) -> Result<String> {
+        let expanded = expand(source, &Markdown, highlighter(), templates)?;
+        Ok(expanded.restore(&Markdown.render(&expanded.source, highlighter())?.html))
+    }
+
+    #[test]
+    fn test_parse_call() {
+        let text = r#"{{ playground(code="give back 1;", height=240, run=true, tags=['a', "b"]) }} rest"#;
+        let call = parse_call(text).unwrap();
+        assert_eq!(call.name, "playground");
+        assert!(!call.block);
+        assert_eq!(
+            Value::Object(call.args),
+            serde_json::json!({ "code": "give back 1;", "height": 240, "run": true, "tags": ["a", "b"] })
+        );
+        assert_eq!(&text[call.len..], " rest");
+
+        assert!(parse_call("{{ page.title }}").is_none());
+        assert!(parse_call("{{ not a call() }}").is_none());
+        assert!(parse_call("{% end %}").is_none());
+        assert!(parse_call("{% note() %}").unwrap().block);
+        assert_eq!(parse_end("{%  end %} rest"), Some(10));
+        assert_eq!(parse_end("{% endless %}"), None);
+    }
+
+    #[test]
+    fn test_expand_and_restore() {
+        let (_site, templates) = templates(&[
+            ("video.html", r#"<video src="{{ src | safe }}"></video>"#),
+            ("note.html", r#"<aside class="{{ kind }}">{{ body | safe }}</aside>#{{ nth }}"#),
+        ]);
+        let source = "Watch {{ video(src=\"/intro.mp4\") }} now.\n\n\
+                      {% note(kind=\"tip\") %}\nBe *kind*.\n{% end %}\n\n\
+                      {% note(kind=\"warn\") %}Careful{% end %}\n\n\
+                      ```\n{{ video(src=\"a\") }}\n```\n\nUse `{{ video(src=\"b\") }}` inline.\n";
+
+        assert_eq!(
+            render(source, &templates).unwrap(),
+            "<p>Watch <video src=\"/intro.mp4\"></video> now.</p>\n\
+             <aside class=\"tip\"><p>Be <em>kind</em>.</p></aside>#1\n\
+             <aside class=\"warn\"><p>Careful</p></aside>#2\n\
+             <pre><code>{{ video(src=\"a\") }}\n</code></pre>\n\
+             <p>Use <code>{{ video(src=\"b\") }}</code> inline.</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_nested_block_shortcodes() {
+        let (_site, templates) = templates(&[
+            ("tabs.html", "<div class=\"tabs\">{{ body | safe }}</div>"),
+            ("tab.html", "<section title=\"{{ title }}\">{{ body | safe }}</section>"),
+        ]);
+        let source = "{% tabs() %}\n\
+                      {% tab(title=\"One\") %}\nFirst **tab**.\n{% end %}\n\n\
+                      {% tab(title=\"Two\") %}\nSecond.\n{% end %}\n\
+                      {% end %}\n\nAfter.\n";
+
+        assert_eq!(
+            render(source, &templates).unwrap(),
+            "<div class=\"tabs\">\
+             <section title=\"One\"><p>First <strong>tab</strong>.</p></section>\n\
+             <section title=\"Two\"><p>Second.</p></section></div>\n\
+             <p>After.</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_indented_and_pre_code_is_left_alone() {
+        let (_site, templates) = templates(&[("video.html", "<video></video>")]);
+        let source = "Example:\n\n    {{ video() }}\n\n\
+                      - item\n\n    {{ video() }}\n\n\
+                      <pre>\n{{ video() }}\n</pre>\n\n\
+                      <pre class=\"x\">{{ video() }}</pre>\n";
+
+        let expanded = expand(source, &Markdown, highlighter(), &templates).unwrap();
+        assert_eq!(expanded.rendered.len(), 1);
+        assert!(expanded.source.starts_with("Example:\n\n    {{ video() }}\n\n- item\n\n    \u{E000}"));
+        assert!(expanded.source.ends_with("<pre>\n{{ video() }}\n</pre>\n\n<pre class=\"x\">{{ video() }}</pre>\n"));
+    }
+
+    #[test]
+    fn test_unknown_shortcodes_and_unclosed_blocks_fail() {
+        let (_site, templates) = templates(&[("note.html", "{{ body }}")]);
+
+        let err = format!("{:#}", render("{{ missing() }}", &templates).err().unwrap());
+        assert!(err.contains("no template shortcodes/missing.html"), "{}", err);
+
+        let err = format!("{:#}", render("{% note() %}\nNever closed\n", &templates).err().unwrap());
+        assert!(err.contains("Shortcode 'note' is missing its {% end %}"), "{}", err);
+
+        let err = format!("{:#}", render("{% note() %}{% note() %}x{% end %}", &templates).err().unwrap());
+        assert!(err.contains("Shortcode 'note' is missing its {% end %}"), "{}", err);
+    }
+}
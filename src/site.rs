@@ -0,0 +1,141 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! The whole site as templates see it
+//!
+//! Once every page has been placed, linked into sections and filed under
+//! taxonomy terms, the builder freezes the result into a `Site`. It's shared
+//! by every render, and templates reach into it with `get_page`,
+//! `get_section` and `get_taxonomy` to build navigation and widgets.
+
+use std::collections::HashMap;
+
+use crate::cache;
+use crate::content::Page;
+use crate::sections::Section;
+use crate::taxonomies::Taxonomy;
+
+pub struct Site {
+    pub pages: Vec<Page>,
+    pub sections: Vec<Section>,
+    pub taxonomies: Vec<Taxonomy>,
+    /// Page indices by source path and by site path
+    page_lookup: HashMap<String, usize>,
+    /// Section indices by source path, directory and site path
+    section_lookup: HashMap<String, usize>,
+    /// `(taxonomy, term)` indices each page is filed under, in order
+    page_terms: Vec<Vec<(usize, usize)>>,
+}
+
+impl Site {
+    pub fn new(pages: Vec<Page>, sections: Vec<Section>, taxonomies: Vec<Taxonomy>) -> Self {
+        let mut page_lookup = HashMap::new();
+        for (i, page) in pages.iter().enumerate() {
+            page_lookup.insert(cache::path_key(&page.relative_path), i);
+            page_lookup.insert(page.path.clone(), i);
+        }
+
+        let mut section_lookup = HashMap::new();
+        for (i, section) in sections.iter().enumerate() {
+            section_lookup.insert(cache::path_key(&section.relative_path), i);
+            section_lookup.insert(section.dir.clone(), i);
+            section_lookup.insert(section.path.clone(), i);
+        }
+
+        let mut page_terms = vec![Vec::new(); pages.len()];
+        for (t, taxonomy) in taxonomies.iter().enumerate() {
+            for (i, term) in taxonomy.terms.iter().enumerate() {
+                for &page in &term.pages {
+                    page_terms[page].push((t, i));
+                }
+            }
+        }
+
+        Self {
+            pages,
+            sections,
+            taxonomies,
+            page_lookup,
+            section_lookup,
+            page_terms,
+        }
+    }
+
+    /// Page with the given source path (`docs/intro.md`, optionally written
+    /// `@/docs/intro.md`) or site path (`/docs/intro.html`)
+    pub fn page(&self, path: &str) -> Option<usize> {
+        self.page_lookup.get(lookup_key(path)).copied()
+    }
+
+    /// Section with the given `_index` file path, directory or site path
+    pub fn section(&self, path: &str) -> Option<usize> {
+        let key = lookup_key(path);
+        self.section_lookup
+            .get(key)
+            .or_else(|| self.section_lookup.get(key.trim_matches('/')))
+            .copied()
+    }
+
+    /// `(taxonomy, term)` indices of the terms `page` is filed under
+    pub fn page_terms(&self, page: usize) -> &[(usize, usize)] {
+        &self.page_terms[page]
+    }
+
+    pub fn taxonomy(&self, name: &str) -> Option<&Taxonomy> {
+        self.taxonomies.iter().find(|t| t.config.name == name)
+    }
+}
+
+fn lookup_key(path: &str) -> &str {
+    path.strip_prefix("@/").unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::config::Config;
+    use crate::sections::SectionFrontMatter;
+    use crate::taxonomies;
+
+    #[test]
+    fn test_lookups_accept_source_and_site_paths() {
+        let mut page = Page::new(serde_yaml::from_str("title: Intro").unwrap(), "", String::new(), String::new());
+        page.relative_path = PathBuf::from("docs/intro.md");
+        page.path = "/docs/intro.html".to_string();
+
+        let mut section = Section::new(SectionFrontMatter::default(), String::new(), Vec::new());
+        section.dir = "docs".to_string();
+        section.relative_path = PathBuf::from("docs/_index.md");
+        section.path = "/docs/".to_string();
+
+        let site = Site::new(vec![page], vec![section], Vec::new());
+        for path in ["docs/intro.md", "@/docs/intro.md", "/docs/intro.html"] {
+            assert_eq!(site.page(path), Some(0), "{path}");
+        }
+        for path in ["docs/_index.md", "@/docs/_index.md", "docs", "/docs/", "docs/"] {
+            assert_eq!(site.section(path), Some(0), "{path}");
+        }
+        assert_eq!(site.page("docs/missing.md"), None);
+        assert_eq!(site.section("blog"), None);
+        assert!(site.taxonomy("tags").is_none());
+    }
+
+    #[test]
+    fn test_page_terms() {
+        let page = |tags: &str| {
+            Page::new(serde_yaml::from_str(&format!("title: Post\ntags: {tags}")).unwrap(), "", String::new(), String::new())
+        };
+        let pages = vec![page("[rust, consent]"), page("[]"), page("[consent]")];
+        let mut config = Config::default_wokelang();
+        config.taxonomies = serde_yaml::from_str("[{ name: tags }]").unwrap();
+        let taxonomies = taxonomies::collect(&config, &pages).unwrap();
+
+        let site = Site::new(pages, Vec::new(), taxonomies);
+        let (consent, rust) = (0, 1);
+        assert_eq!(site.page_terms(0), [(0, consent), (0, rust)]);
+        assert_eq!(site.page_terms(1), []);
+        assert_eq!(site.page_terms(2), [(0, consent)]);
+    }
+}
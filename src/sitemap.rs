@@ -7,13 +7,14 @@
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::Writer;
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{BufWriter, Write};
 use std::path::Path;
 
-use crate::config::Config;
 use crate::content::Page;
+use crate::sections::Section;
+use crate::taxonomies::Taxonomy;
 
-pub fn generate(pages: &[&Page], config: &Config, output_path: &Path) -> Result<()> {
+pub fn generate(pages: &[&Page], sections: &[Section], taxonomies: &[Taxonomy], output_path: &Path) -> Result<()> {
     let file = File::create(output_path.join("sitemap.xml"))?;
     let mut writer = Writer::new(BufWriter::new(file));
 
@@ -27,43 +28,57 @@ pub fn generate(pages: &[&Page], config: &Config, output_path: &Path) -> Result<
 
     // Add pages
     for page in pages {
-        if page.front_matter.draft {
-            continue;
-        }
+        let lastmod = page.front_matter.date.map(|d| d.format("%Y-%m-%d").to_string());
+        let priority = if page.path == "/" { "1.0" } else { "0.8" };
+        write_url(&mut writer, &page.permalink, lastmod.as_deref(), priority)?;
+    }
+
+    // Add section listings
+    for section in sections {
+        let priority = if section.path == "/" { "1.0" } else { "0.8" };
+        write_url(&mut writer, &section.permalink, None, priority)?;
+    }
 
-        // url element
-        writer.write_event(Event::Start(BytesStart::new("url")))?;
-
-        // loc element
-        writer.write_event(Event::Start(BytesStart::new("loc")))?;
-        let url = format!("{}/{}", config.site.url, page.slug);
-        writer.write_event(Event::Text(BytesText::new(&url)))?;
-        writer.write_event(Event::End(BytesEnd::new("loc")))?;
-
-        // lastmod element (if date available)
-        if let Some(date) = page.front_matter.date {
-            writer.write_event(Event::Start(BytesStart::new("lastmod")))?;
-            let date_str = date.format("%Y-%m-%d").to_string();
-            writer.write_event(Event::Text(BytesText::new(&date_str)))?;
-            writer.write_event(Event::End(BytesEnd::new("lastmod")))?;
+    // Add taxonomy overviews and term listings
+    for taxonomy in taxonomies {
+        write_url(&mut writer, &taxonomy.permalink, None, "0.5")?;
+        for term in &taxonomy.terms {
+            write_url(&mut writer, &term.permalink, None, "0.5")?;
         }
+    }
+
+    // Close urlset
+    writer.write_event(Event::End(BytesEnd::new("urlset")))?;
+
+    Ok(())
+}
 
-        // changefreq element
-        writer.write_event(Event::Start(BytesStart::new("changefreq")))?;
-        writer.write_event(Event::Text(BytesText::new("weekly")))?;
-        writer.write_event(Event::End(BytesEnd::new("changefreq")))?;
+fn write_url<W: Write>(writer: &mut Writer<W>, loc: &str, lastmod: Option<&str>, priority: &str) -> Result<()> {
+    // url element
+    writer.write_event(Event::Start(BytesStart::new("url")))?;
 
-        // priority element
-        writer.write_event(Event::Start(BytesStart::new("priority")))?;
-        let priority = if page.slug == "index" { "1.0" } else { "0.8" };
-        writer.write_event(Event::Text(BytesText::new(priority)))?;
-        writer.write_event(Event::End(BytesEnd::new("priority")))?;
+    // loc element
+    writer.write_event(Event::Start(BytesStart::new("loc")))?;
+    writer.write_event(Event::Text(BytesText::new(loc)))?;
+    writer.write_event(Event::End(BytesEnd::new("loc")))?;
 
-        writer.write_event(Event::End(BytesEnd::new("url")))?;
+    // lastmod element (if date available)
+    if let Some(lastmod) = lastmod {
+        writer.write_event(Event::Start(BytesStart::new("lastmod")))?;
+        writer.write_event(Event::Text(BytesText::new(lastmod)))?;
+        writer.write_event(Event::End(BytesEnd::new("lastmod")))?;
     }
 
-    // Close urlset
-    writer.write_event(Event::End(BytesEnd::new("urlset")))?;
+    // changefreq element
+    writer.write_event(Event::Start(BytesStart::new("changefreq")))?;
+    writer.write_event(Event::Text(BytesText::new("weekly")))?;
+    writer.write_event(Event::End(BytesEnd::new("changefreq")))?;
+
+    // priority element
+    writer.write_event(Event::Start(BytesStart::new("priority")))?;
+    writer.write_event(Event::Text(BytesText::new(priority)))?;
+    writer.write_event(Event::End(BytesEnd::new("priority")))?;
 
+    writer.write_event(Event::End(BytesEnd::new("url")))?;
     Ok(())
 }
@@ -0,0 +1,169 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Taxonomies
+//!
+//! A taxonomy groups pages by the terms they list in their front matter, such
+//! as tags or categories. Every taxonomy configured in `config.yaml` gets an
+//! overview of its terms at `/<name>/` and a listing per term at
+//! `/<name>/<term>/`, rendered with `taxonomy_list.html` and
+//! `taxonomy_single.html` unless the site provides `<name>/list.html` or
+//! `<name>/single.html`.
+
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+#[cfg(test)]
+use std::path::Path;
+
+use crate::config::{Config, TaxonomyConfig};
+use crate::content::{slugify, Page};
+use crate::pagination::Paginator;
+use crate::sections::{compare_pages, SortBy};
+
+#[derive(Debug, Clone)]
+pub struct Taxonomy {
+    pub config: TaxonomyConfig,
+    pub slug: String,
+    pub output_path: PathBuf,
+    pub path: String,
+    pub permalink: String,
+    /// Template for the overview of all terms
+    pub list_template: String,
+    /// Template for a single term's listing
+    pub single_template: String,
+    /// Terms ordered by slug
+    pub terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Term {
+    /// Name as first written in a page's front matter
+    pub name: String,
+    pub slug: String,
+    pub output_path: PathBuf,
+    pub path: String,
+    pub permalink: String,
+    /// Pages listing the term, newest first, as page indices
+    pub pages: Vec<usize>,
+}
+
+impl Taxonomy {
+    pub fn paginator(&self, term: &Term) -> Option<Paginator> {
+        Paginator::new(&term.path, term.pages.len(), self.config.paginate_by)
+    }
+}
+
+/// Gather the terms of every configured taxonomy from `pages`.
+///
+/// Terms that slugify the same, like `WokeLang` and `wokelang`, are merged
+/// into one. Templates are left empty for the builder to pick.
+pub fn collect(config: &Config, pages: &[Page]) -> Result<Vec<Taxonomy>> {
+    let base_url = config.site.url.trim_end_matches('/');
+    let mut taxonomies: Vec<Taxonomy> = Vec::new();
+
+    for taxonomy_config in &config.taxonomies {
+        let slug = slugify(&taxonomy_config.name);
+        if slug.is_empty() {
+            bail!("Taxonomy name '{}' has no usable characters", taxonomy_config.name);
+        }
+        if let Some(other) = taxonomies.iter().find(|t| t.slug == slug) {
+            bail!(
+                "Taxonomies '{}' and '{}' would share the URL /{}/",
+                other.config.name,
+                taxonomy_config.name,
+                slug
+            );
+        }
+
+        let path = format!("/{}/", slug);
+        let mut terms: BTreeMap<String, Term> = BTreeMap::new();
+
+        for (index, page) in pages.iter().enumerate() {
+            for name in page.front_matter.terms(&taxonomy_config.name) {
+                let term_slug = slugify(name);
+                if term_slug.is_empty() {
+                    continue;
+                }
+
+                let term = terms.entry(term_slug.clone()).or_insert_with(|| {
+                    let term_path = format!("{}{}/", path, term_slug);
+                    Term {
+                        name: name.trim().to_string(),
+                        output_path: PathBuf::from(&slug).join(&term_slug).join("index.html"),
+                        permalink: format!("{}{}", base_url, term_path),
+                        path: term_path,
+                        slug: term_slug,
+                        pages: Vec::new(),
+                    }
+                });
+                if !term.pages.contains(&index) {
+                    term.pages.push(index);
+                }
+            }
+        }
+
+        let mut terms: Vec<Term> = terms.into_values().collect();
+        for term in &mut terms {
+            term.pages.sort_by(|&a, &b| compare_pages(&pages[a], &pages[b], SortBy::Date));
+        }
+
+        taxonomies.push(Taxonomy {
+            config: taxonomy_config.clone(),
+            output_path: PathBuf::from(&slug).join("index.html"),
+            permalink: format!("{}{}", base_url, path),
+            path,
+            slug,
+            list_template: String::new(),
+            single_template: String::new(),
+            terms,
+        });
+    }
+
+    Ok(taxonomies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(front_matter: &str) -> Page {
+        Page::new(serde_yaml::from_str(front_matter).unwrap(), "", String::new(), String::new())
+    }
+
+    fn config(taxonomies: &str) -> Config {
+        let mut config = Config::default_wokelang();
+        config.taxonomies = serde_yaml::from_str(taxonomies).unwrap();
+        config
+    }
+
+    #[test]
+    fn test_collect_merges_terms_and_orders_pages() {
+        let pages = [
+            page("title: Old\ndate: 2025-01-01T00:00:00Z\ntags: [Consent, Basics, '!!']"),
+            page("title: New\ndate: 2026-01-01T00:00:00Z\ntags: [consent, ' Consent ']\ntaxonomies:\n  wokelang_version: ['0.3']"),
+        ];
+        let config = config("[{ name: tags, feed: true }, { name: wokelang_version }]");
+        let taxonomies = collect(&config, &pages).unwrap();
+
+        let tags = &taxonomies[0];
+        assert_eq!((tags.path.as_str(), tags.output_path.as_path()), ("/tags/", Path::new("tags/index.html")));
+        let terms: Vec<_> = tags.terms.iter().map(|t| (t.name.as_str(), t.path.as_str(), t.pages.clone())).collect();
+        assert_eq!(terms, [("Basics", "/tags/basics/", vec![0]), ("Consent", "/tags/consent/", vec![1, 0])]);
+        assert_eq!(tags.terms[1].permalink, "https://wokelang.org/tags/consent/");
+
+        let versions = &taxonomies[1];
+        assert_eq!(versions.slug, "wokelang-version");
+        assert_eq!(versions.terms[0].slug, "0-3");
+        assert_eq!(versions.terms[0].output_path, Path::new("wokelang-version/0-3/index.html"));
+    }
+
+    #[test]
+    fn test_unusable_taxonomy_names_fail() {
+        let err = collect(&config("[{ name: '??' }]"), &[]).unwrap_err().to_string();
+        assert_eq!(err, "Taxonomy name '??' has no usable characters");
+
+        let err = collect(&config("[{ name: Tags }, { name: tags }]"), &[]).unwrap_err().to_string();
+        assert_eq!(err, "Taxonomies 'Tags' and 'tags' would share the URL /tags/");
+    }
+}
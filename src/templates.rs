@@ -3,22 +3,29 @@
 
 //! Template rendering with Tera
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 use tera::{Tera, Context as TeraContext};
-use std::path::Path;
 
+use crate::assets::{AssetFunction, AssetManifest};
+use crate::bundles;
+use crate::cache;
 use crate::config::Config;
 use crate::content::Page;
+use crate::data::LoadData;
+use crate::images::{ImageProcessor, ResponsiveImageFunction};
+use crate::pagination::Paginator;
+use crate::sections::Section;
+use crate::site::Site;
+use crate::taxonomies::{Taxonomy, Term};
+use crate::toc::Heading;
 
 pub struct Templates {
     tera: Tera,
-}
-
-#[derive(Serialize)]
-struct PageContext<'a> {
-    page: &'a Page,
-    site: &'a crate::config::SiteConfig,
+    /// Contents of the data directory, available to every template as `data`
+    data: tera::Value,
 }
 
 impl Templates {
@@ -27,38 +34,496 @@ pub fn load(templates_dir: &str) -> Result<Self> {
         let tera = Tera::new(&pattern)
             .with_context(|| format!("Failed to load templates from {}", templates_dir))?;
 
-        Ok(Self { tera })
+        Ok(Self {
+            tera,
+            data: tera::Value::Object(Default::default()),
+        })
+    }
+
+    /// Make `asset(path=...)` resolve against this build's asset manifest
+    pub fn register_assets(&mut self, manifest: Arc<AssetManifest>) {
+        self.tera.register_function("asset", AssetFunction::new(manifest));
     }
 
-    pub fn render(&self, template_name: &str, page: &Page, config: &Config) -> Result<String> {
+    /// Expose the data directory as `data` and make `load_data(path=...)`
+    /// read files through `loader`
+    pub fn register_data(&mut self, data: tera::Value, loader: LoadData) {
+        self.data = data;
+        self.tera.register_function("load_data", loader);
+    }
+
+    /// Make `responsive_image(path=...)` write variants through `processor`
+    pub fn register_images(&mut self, processor: Arc<ImageProcessor>) {
+        self.tera.register_function("responsive_image", ResponsiveImageFunction::new(processor));
+    }
+
+    /// `preferred` if the site defines it, otherwise `fallback`
+    pub fn pick(&self, preferred: String, fallback: &str) -> String {
+        if self.tera.get_template_names().any(|name| name == preferred) {
+            preferred
+        } else {
+            fallback.to_string()
+        }
+    }
+
+    /// Make `get_page`, `get_section` and `get_taxonomy` look things up in
+    /// this build's site
+    pub fn register_site(&mut self, site: Arc<Site>) {
+        self.tera.register_function("get_page", GetPage(Arc::clone(&site)));
+        self.tera.register_function("get_section", GetSection(Arc::clone(&site)));
+        self.tera.register_function("get_taxonomy", GetTaxonomy(site));
+    }
+
+    pub fn page_context<'a>(&self, index: usize, site: &'a Site, config: &Config) -> RenderContext<'a> {
         let mut context = TeraContext::new();
 
         // Add page data
-        context.insert("page", &PageContextData {
-            title: &page.front_matter.title,
-            description: &page.front_matter.description,
-            html: &page.html,
-            slug: &page.slug,
-            date: page.front_matter.date.as_ref().map(|d| d.to_rfc3339()),
-            tags: &page.front_matter.tags,
-        });
+        context.insert("page", &PageContextData::new(index, site));
 
         // Add site config
         context.insert("site", &config.site);
 
+        RenderContext::new(&site.pages[index].front_matter.template, context)
+    }
+
+    /// A section, or pager `pager` of its listing when it's paginated
+    pub fn section_context<'a>(
+        &self,
+        index: usize,
+        pager: usize,
+        site: &'a Site,
+        config: &Config,
+    ) -> RenderContext<'a> {
+        let section = &site.sections[index];
+        let mut context = TeraContext::new();
+
+        context.insert("section", &SectionContextData::new(index, site));
+        if let Some(paginator) = section.paginator() {
+            context.insert(
+                "paginator",
+                &PaginatorContextData::new(&paginator, pager, &section.pages, &site.pages),
+            );
+        }
+        context.insert("site", &config.site);
+
+        RenderContext::new(&section.front_matter.template, context)
+    }
+
+    /// Overview of every term in a taxonomy
+    pub fn taxonomy_context<'a>(&self, index: usize, site: &'a Site, config: &Config) -> RenderContext<'a> {
+        let taxonomy = &site.taxonomies[index];
+        let mut context = TeraContext::new();
+        context.insert("taxonomy", &TaxonomyContextData::new(taxonomy, &site.pages));
+        context.insert("site", &config.site);
+
+        RenderContext::new(&taxonomy.list_template, context)
+    }
+
+    /// Listing of the pages filed under one term, split into pagers when the
+    /// taxonomy is paginated
+    pub fn term_context<'a>(
+        &self,
+        index: usize,
+        term_index: usize,
+        pager: usize,
+        site: &'a Site,
+        config: &Config,
+    ) -> RenderContext<'a> {
+        let taxonomy = &site.taxonomies[index];
+        let term = &taxonomy.terms[term_index];
+        let mut context = TeraContext::new();
+        context.insert("taxonomy", &TaxonomySummary::new(taxonomy));
+        context.insert("term", &TermContextData::new(term, &site.pages));
+        if let Some(paginator) = taxonomy.paginator(term) {
+            context.insert(
+                "paginator",
+                &PaginatorContextData::new(&paginator, pager, &term.pages, &site.pages),
+            );
+        }
+        context.insert("site", &config.site);
+
+        RenderContext::new(&taxonomy.single_template, context)
+    }
+
+    /// Render a page, section or listing, with `data` added to its context
+    pub fn render_context(&self, render: RenderContext) -> Result<String> {
+        let mut context = render.context;
+        context.insert("data", &self.data);
+        self.render(render.template, &context)
+    }
+
+    /// Render `shortcodes/<name>.html` for a shortcode used in content
+    pub fn render_shortcode(
+        &self,
+        name: &str,
+        args: &serde_json::Map<String, serde_json::Value>,
+        body: Option<&str>,
+        nth: usize,
+    ) -> Result<String> {
+        let template = format!("shortcodes/{}.html", name);
+        if !self.tera.get_template_names().any(|t| t == template) {
+            bail!("Unknown shortcode '{}': no template {}", name, template);
+        }
+
+        let mut context = TeraContext::from_serialize(args)?;
+        if !args.contains_key("data") {
+            context.insert("data", &self.data);
+        }
+        if let Some(body) = body {
+            context.insert("body", body);
+        }
+        context.insert("nth", &nth);
+
+        self.render(&template, &context)
+    }
+
+    fn render(&self, template_name: &str, context: &TeraContext) -> Result<String> {
         self.tera
-            .render(template_name, &context)
+            .render(template_name, context)
             .with_context(|| format!("Failed to render template: {}", template_name))
     }
 }
 
+/// What a page, section or listing template is rendered with
+pub struct RenderContext<'a> {
+    pub template: &'a str,
+    /// Everything but `data`, which the cache tracks per template instead
+    context: TeraContext,
+}
+
+impl<'a> RenderContext<'a> {
+    fn new(template: &'a str, context: TeraContext) -> Self {
+        Self { template, context }
+    }
+
+    /// Hash of everything the template is shown, so an output is only
+    /// rendered again when what it shows has changed
+    pub fn hash(&self) -> String {
+        cache::hash_bytes(self.context.clone().into_json().to_string().as_bytes())
+    }
+}
+
+/// Tera function `get_page(path="docs/intro.md")`, which also accepts a
+/// page's site path
+struct GetPage(Arc<Site>);
+
+impl tera::Function for GetPage {
+    fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+        let path = string_arg("get_page", "path", args)?;
+        let index = self
+            .0
+            .page(path)
+            .ok_or_else(|| tera::Error::msg(format!("get_page() could not find '{}'", path)))?;
+        Ok(tera::to_value(PageContextData::new(index, &self.0))?)
+    }
+}
+
+/// Tera function `get_section(path="docs/_index.md")`, which also accepts a
+/// section's directory or site path
+struct GetSection(Arc<Site>);
+
+impl tera::Function for GetSection {
+    fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+        let path = string_arg("get_section", "path", args)?;
+        let index = self
+            .0
+            .section(path)
+            .ok_or_else(|| tera::Error::msg(format!("get_section() could not find '{}'", path)))?;
+        Ok(tera::to_value(SectionContextData::new(index, &self.0))?)
+    }
+}
+
+/// Tera function `get_taxonomy(kind="tags")`
+struct GetTaxonomy(Arc<Site>);
+
+impl tera::Function for GetTaxonomy {
+    fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+        let kind = string_arg("get_taxonomy", "kind", args)?;
+        let taxonomy = self
+            .0
+            .taxonomy(kind)
+            .ok_or_else(|| tera::Error::msg(format!("get_taxonomy() found no taxonomy '{}'", kind)))?;
+        Ok(tera::to_value(TaxonomyContextData::new(taxonomy, &self.0.pages))?)
+    }
+}
+
+fn string_arg<'a>(function: &str, name: &str, args: &'a HashMap<String, tera::Value>) -> tera::Result<&'a str> {
+    args.get(name)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| tera::Error::msg(format!("{}() requires a string `{}` argument", function, name)))
+}
+
 #[derive(Serialize)]
 struct PageContextData<'a> {
     title: &'a str,
     description: &'a str,
     html: &'a str,
+    toc: &'a [Heading],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a str>,
+    word_count: usize,
+    reading_time: usize,
+    /// Site paths of the files bundled with the page
+    assets: Vec<String>,
+    slug: &'a str,
+    path: &'a str,
+    permalink: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date: Option<String>,
+    tags: &'a [String],
+    draft: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    banner: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent: Option<SectionSummary<'a>>,
+    ancestors: Vec<SectionSummary<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous: Option<PageSummary<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next: Option<PageSummary<'a>>,
+    /// Terms the page is filed under, keyed by taxonomy name
+    taxonomies: BTreeMap<&'a str, Vec<TermSummary<'a>>>,
+    extra: &'a BTreeMap<String, serde_yaml::Value>,
+}
+
+impl<'a> PageContextData<'a> {
+    fn new(index: usize, site: &'a Site) -> Self {
+        let page = &site.pages[index];
+        let sections = &site.sections;
+        let mut taxonomies: BTreeMap<&str, Vec<TermSummary>> =
+            site.taxonomies.iter().map(|taxonomy| (taxonomy.config.name.as_str(), Vec::new())).collect();
+        for &(t, term) in site.page_terms(index) {
+            let taxonomy = &site.taxonomies[t];
+            let terms = taxonomies.entry(taxonomy.config.name.as_str()).or_default();
+            terms.push(TermSummary::new(&taxonomy.terms[term]));
+        }
+
+        Self {
+            title: &page.front_matter.title,
+            description: &page.front_matter.description,
+            html: &page.html,
+            toc: &page.toc,
+            summary: page.summary.as_deref(),
+            word_count: page.word_count,
+            reading_time: page.reading_time,
+            assets: page.assets.iter().map(|name| bundles::asset_path(page, name)).collect(),
+            slug: &page.slug,
+            path: &page.path,
+            permalink: &page.permalink,
+            date: page.front_matter.date.as_ref().map(|d| d.to_rfc3339()),
+            tags: &page.front_matter.tags,
+            draft: page.front_matter.draft,
+            banner: page.banner.as_deref(),
+            parent: page.parent.map(|i| SectionSummary::new(&sections[i])),
+            ancestors: page.ancestors.iter().map(|&i| SectionSummary::new(&sections[i])).collect(),
+            previous: page.previous.map(|i| PageSummary::new(&site.pages[i])),
+            next: page.next.map(|i| PageSummary::new(&site.pages[i])),
+            taxonomies,
+            extra: &page.front_matter.extra,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SectionContextData<'a> {
+    title: &'a str,
+    description: &'a str,
+    html: &'a str,
+    toc: &'a [Heading],
+    path: &'a str,
+    permalink: &'a str,
+    pages: Vec<PageSummary<'a>>,
+    subsections: Vec<SectionSummary<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent: Option<SectionSummary<'a>>,
+    ancestors: Vec<SectionSummary<'a>>,
+    extra: &'a BTreeMap<String, serde_yaml::Value>,
+}
+
+/// What listings and neighbour links need to know about a page
+#[derive(Serialize)]
+pub struct PageSummary<'a> {
+    title: &'a str,
+    description: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a str>,
+    word_count: usize,
+    reading_time: usize,
+    /// Site paths of the files bundled with the page
+    assets: Vec<String>,
     slug: &'a str,
+    path: &'a str,
+    permalink: &'a str,
     #[serde(skip_serializing_if = "Option::is_none")]
     date: Option<String>,
     tags: &'a [String],
+    draft: bool,
+    extra: &'a BTreeMap<String, serde_yaml::Value>,
+}
+
+impl<'a> PageSummary<'a> {
+    pub fn new(page: &'a Page) -> Self {
+        Self {
+            title: &page.front_matter.title,
+            description: &page.front_matter.description,
+            summary: page.summary.as_deref(),
+            word_count: page.word_count,
+            reading_time: page.reading_time,
+            assets: page.assets.iter().map(|name| bundles::asset_path(page, name)).collect(),
+            slug: &page.slug,
+            path: &page.path,
+            permalink: &page.permalink,
+            date: page.front_matter.date.as_ref().map(|d| d.to_rfc3339()),
+            tags: &page.front_matter.tags,
+            draft: page.front_matter.draft,
+            extra: &page.front_matter.extra,
+        }
+    }
+}
+
+/// What breadcrumbs and subsection listings need to know about a section
+#[derive(Serialize)]
+struct SectionSummary<'a> {
+    title: &'a str,
+    description: &'a str,
+    path: &'a str,
+    permalink: &'a str,
+}
+
+impl<'a> SectionSummary<'a> {
+    fn new(section: &'a Section) -> Self {
+        Self {
+            title: &section.front_matter.title,
+            description: &section.front_matter.description,
+            path: &section.path,
+            permalink: &section.permalink,
+        }
+    }
+}
+
+impl<'a> SectionContextData<'a> {
+    fn new(index: usize, site: &'a Site) -> Self {
+        let section = &site.sections[index];
+        let sections = &site.sections;
+        Self {
+            title: &section.front_matter.title,
+            description: &section.front_matter.description,
+            html: &section.html,
+            toc: &section.toc,
+            path: &section.path,
+            permalink: &section.permalink,
+            pages: section.pages.iter().map(|&i| PageSummary::new(&site.pages[i])).collect(),
+            subsections: section.subsections.iter().map(|&i| SectionSummary::new(&sections[i])).collect(),
+            parent: section.parent.map(|i| SectionSummary::new(&sections[i])),
+            ancestors: section.ancestors.iter().map(|&i| SectionSummary::new(&sections[i])).collect(),
+            extra: &section.front_matter.extra,
+        }
+    }
+}
+
+/// One pager of a paginated listing
+#[derive(Serialize)]
+struct PaginatorContextData<'a> {
+    /// Pages shown on this pager
+    pages: Vec<PageSummary<'a>>,
+    paginate_by: usize,
+    /// Number of this pager, counting from 1
+    current_index: usize,
+    number_pagers: usize,
+    /// Number of pages across all pagers
+    total_pages: usize,
+    first: String,
+    last: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next: Option<String>,
+}
+
+impl<'a> PaginatorContextData<'a> {
+    fn new(paginator: &Paginator, pager: usize, listing: &[usize], pages: &'a [Page]) -> Self {
+        Self {
+            pages: listing[paginator.range(pager)].iter().map(|&i| PageSummary::new(&pages[i])).collect(),
+            paginate_by: paginator.paginate_by,
+            current_index: pager,
+            number_pagers: paginator.number_pagers,
+            total_pages: paginator.total_pages,
+            first: paginator.path(1),
+            last: paginator.path(paginator.number_pagers),
+            previous: (pager > 1).then(|| paginator.path(pager - 1)),
+            next: (pager < paginator.number_pagers).then(|| paginator.path(pager + 1)),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TaxonomyContextData<'a> {
+    #[serde(flatten)]
+    summary: TaxonomySummary<'a>,
+    terms: Vec<TermContextData<'a>>,
+}
+
+impl<'a> TaxonomyContextData<'a> {
+    fn new(taxonomy: &'a Taxonomy, pages: &'a [Page]) -> Self {
+        Self {
+            summary: TaxonomySummary::new(taxonomy),
+            terms: taxonomy.terms.iter().map(|term| TermContextData::new(term, pages)).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TermContextData<'a> {
+    #[serde(flatten)]
+    summary: TermSummary<'a>,
+    pages: Vec<PageSummary<'a>>,
+}
+
+impl<'a> TermContextData<'a> {
+    fn new(term: &'a Term, pages: &'a [Page]) -> Self {
+        Self {
+            summary: TermSummary::new(term),
+            pages: term.pages.iter().map(|&i| PageSummary::new(&pages[i])).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TaxonomySummary<'a> {
+    name: &'a str,
+    path: &'a str,
+    permalink: &'a str,
+}
+
+impl<'a> TaxonomySummary<'a> {
+    fn new(taxonomy: &'a Taxonomy) -> Self {
+        Self {
+            name: &taxonomy.config.name,
+            path: &taxonomy.path,
+            permalink: &taxonomy.permalink,
+        }
+    }
+}
+
+/// What tag clouds and term links need to know about a term
+#[derive(Serialize)]
+struct TermSummary<'a> {
+    name: &'a str,
+    slug: &'a str,
+    path: &'a str,
+    permalink: &'a str,
+    /// Number of pages filed under the term
+    count: usize,
+}
+
+impl<'a> TermSummary<'a> {
+    fn new(term: &'a Term) -> Self {
+        Self {
+            name: &term.name,
+            slug: &term.slug,
+            path: &term.path,
+            permalink: &term.permalink,
+            count: term.pages.len(),
+        }
+    }
 }
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Fixtures shared by the unit tests
+
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tempfile::TempDir;
+
+use crate::builder::{self, BuildOptions, BuildStats};
+use crate::config::{Config, HighlightConfig};
+use crate::content::Page;
+use crate::data::{self, LoadData};
+use crate::highlight::Highlighter;
+use crate::sections::Section;
+use crate::templates::Templates;
+
+/// Write `contents` to `file` under `root`, creating directories as needed
+pub fn write(root: &Path, file: &str, contents: &str) {
+    let path = root.join(file);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// Highlighter for the default themes, loaded once since loading is slow
+pub fn highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| Highlighter::new(&HighlightConfig::default()).unwrap())
+}
+
+/// A site in a temporary directory, laid out like a real one: `content/`,
+/// `templates/`, `static/` and `data/` under the root, built into `public/`
+pub struct TestSite {
+    _dir: TempDir,
+    pub root: PathBuf,
+    pub content: PathBuf,
+    pub output: PathBuf,
+    pub config: Config,
+}
+
+impl TestSite {
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let dir_of = |name: &str| root.join(name).to_string_lossy().into_owned();
+
+        let mut config = Config::default_wokelang();
+        config.build.templates_dir = dir_of("templates");
+        config.build.static_dir = dir_of("static");
+        config.build.cache_dir = dir_of(".cache");
+        config.build.data_dir = dir_of("data");
+
+        Self {
+            content: root.join("content"),
+            output: root.join("public"),
+            root,
+            config,
+            _dir: dir,
+        }
+    }
+
+    /// Write a file, given relative to the site root
+    pub fn write(&self, file: &str, contents: &str) {
+        write(&self.root, file, contents);
+    }
+
+    pub fn build(&self) -> Result<BuildStats> {
+        self.build_with(&BuildOptions::default())
+    }
+
+    pub fn build_with(&self, options: &BuildOptions) -> Result<BuildStats> {
+        self.build_into(&self.output, options)
+    }
+
+    pub fn build_into(&self, output: &Path, options: &BuildOptions) -> Result<BuildStats> {
+        builder::build_site(&self.config, self.content.to_str().unwrap(), output.to_str().unwrap(), options)
+    }
+
+    /// A file the build wrote, given relative to the output directory
+    pub fn read(&self, file: &str) -> String {
+        fs::read_to_string(self.output.join(file)).unwrap()
+    }
+
+    /// The site's templates, with `data` and `load_data` set up the way a
+    /// build sets them up
+    pub fn templates(&self) -> Templates {
+        let data_dir = Path::new(&self.config.build.data_dir);
+        let mut templates = Templates::load(&self.config.build.templates_dir).unwrap();
+        templates.register_data(data::load_dir(data_dir).unwrap(), LoadData::new(data_dir, &self.content));
+        templates
+    }
+
+    /// A page parsed from `content/<file>`, before it's placed in the site
+    pub fn page(&self, file: &str) -> Result<Page> {
+        let anchors = self.config.build.anchor_links;
+        Page::from_file(&self.content.join(file), highlighter(), &self.templates(), anchors)
+    }
+
+    /// A section parsed from `content/<file>`, before it's placed in the site
+    pub fn section(&self, file: &str) -> Result<Section> {
+        let anchors = self.config.build.anchor_links;
+        Section::from_file(&self.content.join(file), highlighter(), &self.templates(), anchors)
+    }
+}
@@ -0,0 +1,188 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! Heading ids and tables of contents
+//!
+//! Runs over rendered HTML, so headings get the same treatment whichever
+//! format a page is written in. Headings without an id get one slugified
+//! from their text, suffixed `-1`, `-2`, ... when a page repeats a heading,
+//! and the headings are gathered into a nested table of contents.
+
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use crate::config::AnchorLinks;
+use crate::content::{html_to_text, slugify};
+
+/// An entry in a page's table of contents
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Heading {
+    pub level: u32,
+    pub id: String,
+    /// Heading text with markup removed
+    pub title: String,
+    /// Headings of a deeper level up to the next one at this level or above
+    pub children: Vec<Heading>,
+}
+
+/// Give every heading in `html` an id, optionally add a `#` link to it, and
+/// return the rewritten HTML with the nested table of contents
+pub fn link_headings(html: &str, anchors: AnchorLinks) -> (String, Vec<Heading>) {
+    static HEADING: OnceLock<Regex> = OnceLock::new();
+    static ID: OnceLock<Regex> = OnceLock::new();
+    let heading = HEADING.get_or_init(|| Regex::new(r"(?s)<h([1-6])((?:\s[^>]*)?)>(.*?)</h[1-6]>").unwrap());
+    let id_attribute = ID.get_or_init(|| Regex::new(r#"(?:^|\s)id="([^"]*)""#).unwrap());
+
+    // Explicit ids are taken first so generated ones never collide with them
+    let mut used: HashSet<String> = heading
+        .captures_iter(html)
+        .filter_map(|caps| id_attribute.captures(&caps[2]).map(|id| id[1].to_string()))
+        .collect();
+
+    let mut headings = Vec::new();
+    let html = heading.replace_all(html, |caps: &Captures| {
+        let level: u32 = caps[1].parse().unwrap_or(1);
+        let attributes = &caps[2];
+        let inner = &caps[3];
+        let title = html_to_text(inner);
+
+        let (id, attributes) = match id_attribute.captures(attributes) {
+            Some(id) => (id[1].to_string(), attributes.to_string()),
+            None => {
+                let id = unique_id(&title, &mut used);
+                (id.clone(), format!(" id=\"{}\"{}", id, attributes))
+            }
+        };
+
+        let anchor = format!("<a class=\"anchor\" href=\"#{}\" aria-hidden=\"true\">#</a>", id);
+        let inner = match anchors {
+            AnchorLinks::None => inner.to_string(),
+            AnchorLinks::Left => format!("{}{}", anchor, inner),
+            AnchorLinks::Right => format!("{} {}", inner, anchor),
+        };
+
+        headings.push(Heading {
+            level,
+            id,
+            title,
+            children: Vec::new(),
+        });
+        format!("<h{0}{1}>{2}</h{0}>", level, attributes, inner)
+    });
+
+    (html.into_owned(), nest(headings))
+}
+
+fn unique_id(title: &str, used: &mut HashSet<String>) -> String {
+    let base = match slugify(title) {
+        slug if slug.is_empty() => "section".to_string(),
+        slug => slug,
+    };
+
+    let mut id = base.clone();
+    let mut n = 0;
+    while used.contains(&id) {
+        n += 1;
+        id = format!("{}-{}", base, n);
+    }
+    used.insert(id.clone());
+    id
+}
+
+/// Turn headings in document order into a tree, each heading owning the
+/// deeper ones that follow it
+fn nest(headings: Vec<Heading>) -> Vec<Heading> {
+    let mut roots: Vec<Heading> = Vec::new();
+    for heading in headings {
+        insert(&mut roots, heading);
+    }
+    roots
+}
+
+fn insert(siblings: &mut Vec<Heading>, heading: Heading) {
+    match siblings.last_mut() {
+        Some(last) if last.level < heading.level => insert(&mut last.children, heading),
+        _ => siblings.push(heading),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestSite;
+
+    #[test]
+    fn test_headings_get_unique_ids_and_nest() {
+        let html = "<h1>Language Reference</h1>\n<h2>Types &amp; Values</h2>\n<h3>Numbers</h3>\n\
+                    <h2 id=\"custom\">Consent</h2>\n<h2>Types &amp; Values</h2>\n<h2><code>give</code> back</h2>\n\
+                    <h1>Language Reference</h1>\n";
+        let (html, toc) = link_headings(html, AnchorLinks::None);
+
+        assert_eq!(
+            html,
+            "<h1 id=\"language-reference\">Language Reference</h1>\n\
+             <h2 id=\"types-values\">Types &amp; Values</h2>\n<h3 id=\"numbers\">Numbers</h3>\n\
+             <h2 id=\"custom\">Consent</h2>\n<h2 id=\"types-values-1\">Types &amp; Values</h2>\n\
+             <h2 id=\"give-back\"><code>give</code> back</h2>\n\
+             <h1 id=\"language-reference-1\">Language Reference</h1>\n"
+        );
+
+        let outline: Vec<(&str, Vec<&str>)> = toc
+            .iter()
+            .map(|h| (h.id.as_str(), h.children.iter().map(|c| c.id.as_str()).collect()))
+            .collect();
+        assert_eq!(
+            outline,
+            [
+                ("language-reference", vec!["types-values", "custom", "types-values-1", "give-back"]),
+                ("language-reference-1", vec![]),
+            ]
+        );
+        assert_eq!(toc[0].children[0].title, "Types & Values");
+        assert_eq!(toc[0].children[0].children[0].id, "numbers");
+    }
+
+    #[test]
+    fn test_anchor_links() {
+        let (left, _) = link_headings("<h2>Setup</h2>", AnchorLinks::Left);
+        assert_eq!(
+            left,
+            "<h2 id=\"setup\"><a class=\"anchor\" href=\"#setup\" aria-hidden=\"true\">#</a>Setup</h2>"
+        );
+
+        let (right, _) = link_headings("<h2 id=\"go\" class=\"x\">Setup</h2>", AnchorLinks::Right);
+        assert_eq!(
+            right,
+            "<h2 id=\"go\" class=\"x\">Setup <a class=\"anchor\" href=\"#go\" aria-hidden=\"true\">#</a></h2>"
+        );
+    }
+
+    #[test]
+    fn test_data_attributes_are_not_ids() {
+        let (html, toc) = link_headings("<h2 data-id=\"x\">Setup</h2>", AnchorLinks::None);
+        assert_eq!(html, "<h2 id=\"setup\" data-id=\"x\">Setup</h2>");
+        assert_eq!(toc[0].id, "setup");
+    }
+
+    #[test]
+    fn test_pages_get_a_toc_in_every_format() {
+        let mut site = TestSite::new();
+        site.config.build.anchor_links = AnchorLinks::Left;
+        site.write(
+            "content/reference.md",
+            "---\ntitle: Reference\n---\n## Types\n### Numbers\n### Strings & Text\n## Types\n",
+        );
+        site.write("content/guide.adoc", "= Guide\n\n== Install\n\n=== From source\n");
+
+        let outline = |file: &str| -> String {
+            let page = site.page(file).unwrap();
+            let children = |h: &Heading| h.children.iter().map(|c| format!("{};", c.title)).collect::<String>();
+            page.toc.iter().map(|h| format!("{}({})", h.id, children(h))).collect()
+        };
+        assert_eq!(outline("reference.md"), "types(Numbers;Strings & Text;)types-1()");
+        assert_eq!(outline("guide.adoc"), "install(From source;)");
+        assert!(site.page("guide.adoc").unwrap().html.contains("<a class=\"anchor\" href=\"#install\""));
+    }
+}
@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+// SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell
+
+//! File watching for the development server
+
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tower_livereload::Reloader;
+
+use crate::builder::{self, BuildOptions};
+use crate::config::Config;
+
+/// How long the filesystem must stay quiet before a rebuild starts
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watch the site sources and rebuild into `output` whenever they change.
+///
+/// The returned watcher must be kept alive for as long as changes should be
+/// picked up; dropping it stops the background rebuild thread.
+pub fn watch(
+    config_path: &str,
+    source: &str,
+    output: &str,
+    options: BuildOptions,
+    reloader: Reloader,
+) -> Result<Arc<Mutex<RecommendedWatcher>>> {
+    let config = Config::load(config_path)?;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("Failed to start file watcher")?;
+
+    // Directories that don't exist yet, like a site's first `data/`, are
+    // noticed through their closest existing parent and watched once created
+    let mut roots = Vec::new();
+    let mut missing = Vec::new();
+    for dir in [
+        source,
+        config.build.templates_dir.as_str(),
+        config.build.static_dir.as_str(),
+        config.build.data_dir.as_str(),
+    ] {
+        let path = absolute(Path::new(dir));
+        let (watched, mode) = if path.exists() {
+            (path.as_path(), RecursiveMode::Recursive)
+        } else {
+            missing.push(path.clone());
+            (existing_ancestor(&path), RecursiveMode::NonRecursive)
+        };
+        watcher
+            .watch(watched, mode)
+            .with_context(|| format!("Failed to watch {}", dir))?;
+        roots.push(path);
+    }
+
+    // Watch the config file's directory rather than the file itself, since
+    // editors often save by replacing the file, which drops a direct watch
+    let config_file = absolute(Path::new(config_path));
+    let config_dir = config_file.parent().unwrap_or(Path::new(".")).to_path_buf();
+    watcher
+        .watch(&config_dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch {}", config_path))?;
+
+    let filter = ChangeFilter {
+        roots,
+        config_file,
+        output: absolute(Path::new(output)),
+    };
+    let config_path = config_path.to_string();
+    let source = source.to_string();
+    let output = output.to_string();
+    let watcher = Arc::new(Mutex::new(watcher));
+    // Weak, so dropping the returned watcher still ends the thread
+    let handle = Arc::downgrade(&watcher);
+
+    thread::spawn(move || {
+        while let Ok(event) = rx.recv() {
+            if !event.as_ref().is_ok_and(|e| filter.is_relevant(e)) {
+                continue;
+            }
+
+            // Swallow the burst of events a single save tends to produce
+            while rx.recv_timeout(DEBOUNCE).is_ok() {}
+
+            if let Some(watcher) = handle.upgrade() {
+                let mut watcher = watcher.lock().unwrap();
+                missing.retain(|root| {
+                    let created = root.exists();
+                    if created {
+                        if let Err(e) = watcher.watch(root, RecursiveMode::Recursive) {
+                            eprintln!("✗ Failed to watch {}: {}", root.display(), e);
+                        }
+                    }
+                    !created
+                });
+            }
+
+            println!("↻ Change detected, rebuilding...");
+            match rebuild(&config_path, &source, &output, &options) {
+                Ok(()) => reloader.reload(),
+                Err(e) => eprintln!("✗ Rebuild failed: {:#}", e),
+            }
+        }
+    });
+
+    Ok(watcher)
+}
+
+fn rebuild(config_path: &str, source: &str, output: &str, options: &BuildOptions) -> Result<()> {
+    let config = Config::load(config_path)?;
+    builder::build_site(&config, source, output, options)?;
+    Ok(())
+}
+
+struct ChangeFilter {
+    roots: Vec<PathBuf>,
+    config_file: PathBuf,
+    output: PathBuf,
+}
+
+impl ChangeFilter {
+    fn is_relevant(&self, event: &Event) -> bool {
+        if matches!(event.kind, EventKind::Access(_)) {
+            return false;
+        }
+
+        event.paths.iter().any(|path| {
+            !path.starts_with(&self.output)
+                && !is_editor_file(path)
+                && (path == &self.config_file
+                    || self.roots.iter().any(|root| path.starts_with(root)))
+        })
+    }
+}
+
+/// Swap, backup and lock files editors write next to the file being edited
+fn is_editor_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    name.ends_with('~')
+        || name.starts_with(".#")
+        || (name.starts_with('#') && name.ends_with('#'))
+        // Vim checks that a directory is writable by creating this file
+        || name == "4913"
+        || matches!(extension, "swp" | "swo" | "swx" | "tmp")
+}
+
+/// `path` made absolute, resolving symlinks in whatever part of it exists
+fn absolute(path: &Path) -> PathBuf {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+    let ancestor = existing_ancestor(&path);
+    match ancestor.canonicalize() {
+        Ok(resolved) => resolved.join(path.strip_prefix(ancestor).unwrap_or(Path::new(""))),
+        Err(_) => path.clone(),
+    }
+}
+
+/// The deepest directory or file along `path` that exists
+fn existing_ancestor(path: &Path) -> &Path {
+    path.ancestors().find(|p| p.exists()).unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, ModifyKind};
+
+    fn filter(root: &Path) -> ChangeFilter {
+        ChangeFilter {
+            roots: vec![root.join("content"), root.join("data")],
+            config_file: root.join("config.yaml"),
+            output: root.join("content/public"),
+        }
+    }
+
+    fn event(kind: EventKind, path: PathBuf) -> Event {
+        Event::new(kind).add_path(path)
+    }
+
+    #[test]
+    fn test_changes_under_roots_and_to_the_config_are_relevant() {
+        let root = Path::new("/site");
+        let filter = filter(root);
+        let modify = EventKind::Modify(ModifyKind::Any);
+
+        assert!(filter.is_relevant(&event(modify, root.join("content/intro.md"))));
+        assert!(filter.is_relevant(&event(modify, root.join("config.yaml"))));
+        // A root that didn't exist at startup, created later
+        assert!(filter.is_relevant(&event(EventKind::Create(CreateKind::Folder), root.join("data"))));
+
+        assert!(!filter.is_relevant(&event(modify, root.join("README.md"))));
+        assert!(!filter.is_relevant(&event(modify, root.join("content/public/index.html"))));
+        assert!(!filter.is_relevant(&event(EventKind::Access(AccessKind::Any), root.join("content/intro.md"))));
+    }
+
+    #[test]
+    fn test_editor_files_are_ignored() {
+        let root = Path::new("/site");
+        let filter = filter(root);
+        let modify = EventKind::Modify(ModifyKind::Any);
+
+        for name in ["intro.md~", ".#intro.md", "#intro.md#", ".intro.md.swp", ".intro.md.swx", "4913", "x.tmp"] {
+            assert!(!filter.is_relevant(&event(modify, root.join("content").join(name))), "{}", name);
+        }
+        assert!(filter.is_relevant(&event(modify, root.join("content/swp.md"))));
+    }
+
+    #[test]
+    fn test_absolute_handles_missing_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let resolved = dir.path().canonicalize().unwrap();
+
+        assert_eq!(absolute(&dir.path().join("data/nested")), resolved.join("data/nested"));
+        assert_eq!(existing_ancestor(&resolved.join("data/nested")), resolved);
+    }
+}